@@ -0,0 +1,495 @@
+pub mod template;
+
+use std::{collections::HashSet, fs, path::Path};
+
+use serde::Serialize;
+
+use crate::config::{Config, PackageConfig};
+use crate::forge::Forge;
+use crate::git::tag::TagFormat;
+use crate::helper::Section;
+use template::Notes;
+
+const HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+";
+
+const UNRELEASED: &str = "Unreleased";
+
+/// A changelog in Keep a Changelog style. Only the `Unreleased` section and the links are
+/// rewritten; the header and released sections are kept exactly as written.
+struct Document {
+    /// Everything before the first section
+    header: String,
+    /// The notes under `## [Unreleased]`
+    unreleased: String,
+    /// The released sections, newest first
+    releases: String,
+    /// The link reference definitions at the bottom, e.g. `[1.2.0]: https://...`
+    links: Vec<(String, String)>,
+}
+
+impl Document {
+    fn parse(content: &str) -> Self {
+        let link = regex::Regex::new(r"^\[([^\]]+)\]:\s*(\S+)\s*$").unwrap();
+
+        let mut lines = content.lines().collect::<Vec<&str>>();
+
+        // Link definitions are the trailing block of `[label]: url` lines
+        let mut links = Vec::new();
+        while let Some(line) = lines.last() {
+            if line.trim().is_empty() {
+                lines.pop();
+            } else if let Some(captures) = link.captures(line) {
+                links.insert(0, (captures[1].to_string(), captures[2].to_string()));
+                lines.pop();
+            } else {
+                break;
+            }
+        }
+
+        let sections = lines
+            .iter()
+            .position(|line| line.starts_with("## "))
+            .unwrap_or(lines.len());
+        let header = lines[..sections].join("\n");
+        let mut rest = &lines[sections..];
+
+        let mut unreleased = String::new();
+        if rest.first().is_some_and(|line| label(line) == UNRELEASED) {
+            let end = rest[1..]
+                .iter()
+                .position(|line| line.starts_with("## "))
+                .map(|end| end + 1)
+                .unwrap_or(rest.len());
+
+            unreleased = rest[1..end].join("\n");
+            rest = &rest[end..];
+        }
+
+        Self {
+            header,
+            unreleased: unreleased.trim().to_string(),
+            releases: rest.join("\n").trim().to_string(),
+            links,
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut content = format!("{}\n\n## [{}]\n\n", self.header.trim_end(), UNRELEASED);
+
+        if !self.unreleased.is_empty() {
+            content.push_str(&format!("{}\n\n", self.unreleased));
+        }
+
+        if !self.releases.is_empty() {
+            content.push_str(&format!("{}\n\n", self.releases));
+        }
+
+        for (label, url) in &self.links {
+            content.push_str(&format!("[{}]: {}\n", label, url));
+        }
+
+        format!("{}\n", content.trim_end())
+    }
+
+    /// Sets a link, placing `Unreleased` first and new versions right after it.
+    fn set_link(&mut self, label: &str, url: Option<String>) {
+        self.links.retain(|(existing, _)| existing != label);
+
+        let Some(url) = url else {
+            return;
+        };
+
+        let index = match self.links.first() {
+            _ if label == UNRELEASED => 0,
+            Some((first, _)) if first == UNRELEASED => 1,
+            _ => 0,
+        };
+
+        self.links.insert(index, (label.to_string(), url));
+    }
+}
+
+/// Returns the label of a section heading, e.g. `1.2.0` for `## [1.2.0] - 2024-01-01`.
+fn label(heading: &str) -> &str {
+    let heading = heading.trim_start_matches('#').trim();
+
+    match heading
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+    {
+        Some((label, _)) => label,
+        None => heading.split_whitespace().next().unwrap_or_default(),
+    }
+}
+
+/// Replaces the notes under `## [Unreleased]`, creating the changelog if it doesn't exist yet.
+/// `base` is the latest release tag, which the unreleased changes are compared against.
+pub fn unreleased(
+    path: &Path,
+    notes: &str,
+    base: Option<&str>,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let mut document = match fs::read_to_string(path) {
+        Ok(content) => Document::parse(&content),
+        Err(_) => Document::parse(HEADER),
+    };
+
+    document.unreleased = notes.trim().to_string();
+
+    let forge = Forge::detect(&config.forge)?;
+    document.set_link(
+        UNRELEASED,
+        forge
+            .zip(base)
+            .map(|(forge, base)| forge.compare(base, "HEAD")),
+    );
+
+    fs::write(path, document.render())?;
+
+    Ok(())
+}
+
+/// Adds a dated section for a release to an existing changelog, replacing the unreleased notes.
+/// Returns whether the changelog exists, as it's only maintained once it has been created.
+pub fn release(
+    path: &Path,
+    version: &semver::Version,
+    notes: &str,
+    tag: &str,
+    base: Option<&str>,
+    config: &Config,
+) -> anyhow::Result<bool> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(false);
+    };
+
+    let mut document = Document::parse(&content);
+
+    let date = today();
+    let section = format!("## [{}] - {}\n\n{}", version, date, notes.trim());
+
+    document.unreleased.clear();
+    document.releases = format!("{}\n\n{}", section, document.releases)
+        .trim()
+        .to_string();
+
+    let forge = Forge::detect(&config.forge)?;
+    document.set_link(
+        UNRELEASED,
+        forge.as_ref().map(|forge| forge.compare(tag, "HEAD")),
+    );
+    document.set_link(
+        &version.to_string(),
+        forge.as_ref().map(|forge| release_link(forge, base, tag)),
+    );
+
+    fs::write(path, document.render())?;
+
+    Ok(true)
+}
+
+/// A release in the changelog history, or the changes since the latest release.
+#[derive(Serialize)]
+pub struct Release {
+    /// `None` for the unreleased changes
+    pub version: Option<String>,
+    pub tag: Option<String>,
+    /// The tag of the release before, if any
+    pub previous: Option<String>,
+    pub date: Option<String>,
+    pub notes: String,
+    pub commits: Vec<Entry>,
+}
+
+/// A commit listed in the release notes.
+#[derive(Serialize)]
+pub struct Entry {
+    pub hash: String,
+    pub subject: String,
+    pub emoji: Option<String>,
+    pub scope: Option<String>,
+    pub message: String,
+    pub section: Section,
+}
+
+/// Classifies the commits between each pair of consecutive stable release tags,
+/// and those since the latest one. Returns the newest first, starting with the unreleased changes.
+pub fn history(
+    format: &TagFormat,
+    package: Option<&PackageConfig>,
+    config: &Config,
+) -> anyhow::Result<Vec<Release>> {
+    let tags = crate::git::tag::releases(format)?
+        .into_iter()
+        .filter(|tag| tag.version.pre.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut history = Vec::new();
+    let mut previous: Option<&str> = None;
+    // Built up release by release, instead of reading the history again for each one
+    let mut contributed = HashSet::new();
+
+    for tag in &tags {
+        let commits = crate::helper::commits_between(previous, &tag.name, package)?;
+
+        let notes = Notes {
+            version: Some(&tag.version),
+            tag: Some(&tag.name),
+            previous,
+            date: Some(tag.date.clone()),
+            note: None,
+            commits: &commits,
+            contributed: Some(&contributed),
+        };
+        history.push(entry(&notes, config)?);
+
+        if config.changelog.contributors {
+            // Contributions anywhere in the repository count, not only to the package
+            match package {
+                Some(_) => contributed.extend(template::identities(
+                    &crate::git::log::commits_between(previous, &tag.name)?,
+                )),
+                None => contributed.extend(template::identities(&commits)),
+            }
+        }
+
+        previous = Some(&tag.name);
+    }
+
+    let commits = crate::helper::commits_between(previous, "HEAD", package)?;
+    let notes = Notes {
+        version: None,
+        tag: None,
+        previous,
+        date: None,
+        note: None,
+        commits: &commits,
+        contributed: Some(&contributed),
+    };
+    history.push(entry(&notes, config)?);
+
+    history.reverse();
+
+    Ok(history)
+}
+
+fn entry(notes: &Notes, config: &Config) -> anyhow::Result<Release> {
+    Ok(Release {
+        version: notes.version.map(|version| version.to_string()),
+        tag: notes.tag.map(|tag| tag.to_string()),
+        previous: notes.previous.map(|previous| previous.to_string()),
+        date: notes.date.clone(),
+        notes: template::render(notes, config)?,
+        commits: notes
+            .commits
+            .iter()
+            .filter_map(|commit| {
+                Some(Entry {
+                    section: Section::of(commit, &config.changelog)?,
+                    hash: commit.hash.clone(),
+                    subject: commit.subject.clone(),
+                    emoji: commit.emoji.clone(),
+                    scope: commit.scope.clone(),
+                    message: commit.message.clone(),
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Renders the history as a complete changelog, with a compare link for every release.
+pub fn render(history: &[Release], config: &Config) -> anyhow::Result<String> {
+    let mut document = Document::parse(HEADER);
+    let forge = Forge::detect(&config.forge)?;
+
+    let mut releases = Vec::new();
+
+    // Oldest first, so every link is inserted above the older ones
+    for release in history.iter().rev() {
+        let (Some(version), Some(tag)) = (&release.version, &release.tag) else {
+            document.unreleased = release.notes.trim().to_string();
+            document.set_link(
+                UNRELEASED,
+                forge
+                    .as_ref()
+                    .zip(release.previous.as_ref())
+                    .map(|(forge, base)| forge.compare(base, "HEAD")),
+            );
+            continue;
+        };
+
+        releases.push(format!(
+            "## [{}] - {}\n\n{}",
+            version,
+            release.date.as_deref().unwrap_or_default(),
+            release.notes.trim()
+        ));
+        document.set_link(
+            version,
+            forge
+                .as_ref()
+                .map(|forge| release_link(forge, release.previous.as_deref(), tag)),
+        );
+    }
+
+    releases.reverse();
+    document.releases = releases.join("\n\n");
+
+    Ok(document.render())
+}
+
+/// Links a release to its changes since the release before, or to the tag for the first one.
+fn release_link(forge: &Forge, base: Option<&str>, tag: &str) -> String {
+    match base {
+        Some(base) => forge.compare(base, tag),
+        None => forge.tag(tag),
+    }
+}
+
+/// Returns today's date, as used in release headings, e.g. `2024-01-31`.
+pub fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog
+
+Written by hand.
+
+## [Unreleased]
+
+### Added
+
+- Something new
+
+## [1.1.0] - 2024-02-01
+
+### Fixed
+
+- A bug
+
+## 1.0.0 - 2024-01-01
+
+Initial release.
+
+[Unreleased]: https://github.com/owner/repo/compare/v1.1.0...HEAD
+[1.1.0]: https://github.com/owner/repo/compare/v1.0.0...v1.1.0
+";
+
+    #[test]
+    fn round_trips_a_changelog() {
+        let document = Document::parse(CHANGELOG);
+
+        assert_eq!(document.header, "# Changelog\n\nWritten by hand.\n");
+        assert_eq!(document.unreleased, "### Added\n\n- Something new");
+        assert!(document.releases.starts_with("## [1.1.0] - 2024-02-01"));
+        assert!(document.releases.ends_with("Initial release."));
+        assert_eq!(
+            document.links,
+            [
+                (
+                    "Unreleased".to_string(),
+                    "https://github.com/owner/repo/compare/v1.1.0...HEAD".to_string()
+                ),
+                (
+                    "1.1.0".to_string(),
+                    "https://github.com/owner/repo/compare/v1.0.0...v1.1.0".to_string()
+                ),
+            ]
+        );
+        assert_eq!(document.render(), CHANGELOG);
+    }
+
+    #[test]
+    fn parses_a_changelog_without_unreleased_notes() {
+        let document = Document::parse(HEADER);
+
+        assert_eq!(document.header, HEADER.trim_end());
+        assert!(document.unreleased.is_empty());
+        assert!(document.releases.is_empty());
+        assert!(document.links.is_empty());
+        assert_eq!(document.render(), format!("{}\n## [Unreleased]\n", HEADER));
+    }
+
+    #[test]
+    fn orders_links() {
+        let mut document = Document::parse("# Changelog\n\n[1.0.0]: https://one\n");
+
+        document.set_link("1.1.0", Some("https://two".to_string()));
+        document.set_link(UNRELEASED, Some("https://head".to_string()));
+        document.set_link("1.2.0", Some("https://three".to_string()));
+        document.set_link(UNRELEASED, Some("https://new-head".to_string()));
+
+        let labels = document
+            .links
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["Unreleased", "1.2.0", "1.1.0", "1.0.0"]);
+        assert_eq!(document.links[0].1, "https://new-head");
+
+        document.set_link(UNRELEASED, None);
+        assert_eq!(document.links[0].0, "1.2.0");
+    }
+
+    #[test]
+    fn lists_commits_without_a_trailer_under_other_everywhere() {
+        let commits = [
+            crate::git::log::Commit::from_log("✨ add".to_string(), "semver: minor".to_string()),
+            crate::git::log::Commit::from_log("Update README".to_string(), String::new()),
+        ];
+        let config = Config::default();
+        let version = semver::Version::new(1, 0, 0);
+
+        // As for annotated tags and the full history
+        let release = entry(
+            &Notes {
+                version: Some(&version),
+                tag: Some("v1.0.0"),
+                previous: None,
+                date: None,
+                note: None,
+                commits: &commits,
+                contributed: None,
+            },
+            &config,
+        )
+        .unwrap();
+        // As for the unreleased changes
+        let unreleased = template::render(
+            &Notes {
+                version: None,
+                tag: None,
+                previous: None,
+                date: None,
+                note: None,
+                commits: &commits,
+                contributed: None,
+            },
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(release.commits[1].section, Section::Other);
+        assert!(release.notes.contains("### Other\n- Update README"));
+        assert!(unreleased.contains("### Other\n- Update README"));
+    }
+
+    #[test]
+    fn labels_headings() {
+        assert_eq!(label("## [1.2.0] - 2024-01-01"), "1.2.0");
+        assert_eq!(label("## [Unreleased]"), "Unreleased");
+        assert_eq!(label("## 1.0.0 - 2024-01-01"), "1.0.0");
+        assert_eq!(label("##"), "");
+    }
+}
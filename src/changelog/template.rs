@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Context as _;
+use minijinja::{Environment, Value};
+use serde::Serialize;
+
+use crate::config::{ChangelogConfig, Config};
+use crate::emoji::Emoji;
+use crate::forge::Forge;
+use crate::git::log::{Commit, Person};
+use crate::helper::Section;
+
+/// The layout committer has always used, for when no template is configured.
+const DEFAULT: &str = include_str!("template.md");
+
+/// The release a changelog is rendered for.
+pub struct Notes<'a> {
+    /// `None` for the unreleased changes
+    pub version: Option<&'a semver::Version>,
+    pub tag: Option<&'a str>,
+    /// The tag of the release before, if any
+    pub previous: Option<&'a str>,
+    pub date: Option<String>,
+    /// Why the version was chosen by hand, if it was
+    pub note: Option<String>,
+    pub commits: &'a [Commit],
+    /// The identities of everyone who contributed before the previous release, if already known.
+    /// Otherwise they are looked up when contributors are listed.
+    pub contributed: Option<&'a HashSet<String>>,
+}
+
+/// The variables available in a template.
+#[derive(Serialize)]
+struct Context {
+    version: Option<String>,
+    tag: Option<String>,
+    previous: Option<String>,
+    date: Option<String>,
+    note: Option<String>,
+    /// The forge's view of the changes since the previous release, if the repository is hosted
+    compare_url: Option<String>,
+    /// Every commit in the release, newest first
+    commits: Vec<CommitData>,
+    /// The commits grouped by their bump level or configured intention section
+    sections: Vec<SectionData>,
+    /// Whether the sections should be rendered grouped by scope
+    group_by_scope: bool,
+    /// The commits grouped by intention name, e.g. `bug`
+    intentions: Vec<Group>,
+    /// The commits with a scope, grouped by it
+    scopes: Vec<Group>,
+    /// The authors and co-authors of the commits, when contributors are enabled
+    contributors: Vec<Contributor>,
+}
+
+#[derive(Serialize, Clone)]
+struct CommitData {
+    hash: String,
+    short_hash: String,
+    subject: String,
+    emoji: Option<String>,
+    intention: Option<String>,
+    scope: Option<String>,
+    message: String,
+    body: String,
+    bump: Option<String>,
+    section: Option<Section>,
+    author: Option<Person>,
+    co_authors: Vec<Person>,
+    /// The trailers by lowercase key, e.g. `semver` or `co-authored-by`
+    trailers: BTreeMap<String, Vec<String>>,
+    /// The commit on the forge, if the repository is hosted
+    url: Option<String>,
+    /// The issues referenced in `Refs:` trailers
+    refs: Vec<Reference>,
+}
+
+#[derive(Serialize, Clone)]
+struct Reference {
+    /// The issue as written, without a leading `#`
+    id: String,
+    /// The issue on the forge, if the repository is hosted and the id is a number
+    url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Group {
+    title: String,
+    commits: Vec<CommitData>,
+}
+
+#[derive(Serialize)]
+struct Contributor {
+    name: String,
+    email: String,
+    /// The number of commits they authored or co-authored
+    commits: usize,
+    /// Whether they have no commits before the previous release
+    first_time: bool,
+}
+
+#[derive(Serialize)]
+struct SectionData {
+    title: String,
+    commits: Vec<CommitData>,
+    /// The commits without a scope
+    unscoped: Vec<CommitData>,
+    /// The commits with a scope, grouped by it
+    scopes: Vec<Group>,
+}
+
+/// Renders release notes with the configured template, or the default layout.
+/// Commits, issues and compare views are linked when the repository is hosted on a forge.
+pub fn render(notes: &Notes, config: &Config) -> anyhow::Result<String> {
+    let source = match &config.changelog.template {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the template {}", path.display()))?,
+        None => DEFAULT.to_string(),
+    };
+
+    let mut environment = Environment::new();
+    environment.set_trim_blocks(true);
+    environment.set_lstrip_blocks(true);
+
+    let forge = Forge::detect(&config.forge)?;
+
+    // Turns `#123` into a link to the issue, or leaves the text as is without a forge
+    let issues = forge.clone();
+    environment.add_filter("issues", move |text: String| match &issues {
+        Some(forge) => link_issues(&text, forge),
+        None => text,
+    });
+
+    let changelog = environment
+        .render_str(
+            &source,
+            Value::from_serialize(context(notes, forge.as_ref(), &config.changelog)?),
+        )
+        .context("Failed to render the changelog template")?;
+
+    Ok(changelog)
+}
+
+fn context(
+    notes: &Notes,
+    forge: Option<&Forge>,
+    config: &ChangelogConfig,
+) -> anyhow::Result<Context> {
+    let emojis = Emoji::all();
+
+    let commits = notes
+        .commits
+        .iter()
+        .map(|commit| commit_data(commit, &emojis, forge, config))
+        .collect::<Vec<_>>();
+
+    let sections = Section::all(config)
+        .into_iter()
+        .map(|section| {
+            // Grouped by title, so a configured section can share a built-in one
+            let commits = commits
+                .iter()
+                .filter(|commit| {
+                    commit
+                        .section
+                        .as_ref()
+                        .is_some_and(|s| s.title() == section.title())
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            SectionData {
+                title: section.title().to_string(),
+                unscoped: commits
+                    .iter()
+                    .filter(|commit| commit.scope.is_none())
+                    .cloned()
+                    .collect(),
+                scopes: group_by(&commits, |commit| commit.scope.clone()),
+                commits,
+            }
+        })
+        .filter(|section| !section.commits.is_empty())
+        .collect();
+
+    let contributors = match (config.contributors, notes.contributed) {
+        (true, Some(contributed)) => contributors(notes.commits, contributed),
+        (true, None) => contributors(notes.commits, &contributed_before(notes.previous)?),
+        (false, _) => Vec::new(),
+    };
+
+    Ok(Context {
+        version: notes.version.map(|version| version.to_string()),
+        tag: notes.tag.map(|tag| tag.to_string()),
+        previous: notes.previous.map(|previous| previous.to_string()),
+        date: notes.date.clone(),
+        note: notes.note.clone(),
+        compare_url: forge
+            .zip(notes.previous)
+            .map(|(forge, previous)| forge.compare(previous, notes.tag.unwrap_or("HEAD"))),
+        sections,
+        group_by_scope: config.group_by_scope,
+        intentions: group_by(&commits, |commit| commit.intention.clone()),
+        scopes: group_by(&commits, |commit| commit.scope.clone()),
+        contributors,
+        commits,
+    })
+}
+
+/// Lists everyone who authored or co-authored the commits by name, once per identity.
+/// They are contributing for the first time if they aren't among those who `contributed` before.
+fn contributors(commits: &[Commit], contributed: &HashSet<String>) -> Vec<Contributor> {
+    let mut contributors: Vec<Contributor> = Vec::new();
+
+    for commit in commits {
+        // Someone can be both the author and a co-author of a commit
+        let mut people = commit.contributors().collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        people.retain(|person| seen.insert(identity(person)));
+
+        for person in people {
+            match contributors
+                .iter_mut()
+                .find(|c| c.email.eq_ignore_ascii_case(&person.email))
+            {
+                Some(contributor) => contributor.commits += 1,
+                None => contributors.push(Contributor {
+                    name: person.name.clone(),
+                    email: person.email.clone(),
+                    commits: 1,
+                    first_time: !contributed.contains(&identity(person)),
+                }),
+            }
+        }
+    }
+
+    contributors.sort_by_key(|contributor| contributor.name.to_lowercase());
+
+    contributors
+}
+
+/// Returns the identities of everyone who contributed to the given release or before it.
+pub fn contributed_before(tag: Option<&str>) -> anyhow::Result<HashSet<String>> {
+    let Some(tag) = tag else {
+        return Ok(HashSet::new());
+    };
+
+    let commits = crate::git::log::commits_between(None, tag)?;
+
+    Ok(identities(&commits))
+}
+
+/// Returns the identities of the authors and co-authors of the commits.
+pub fn identities(commits: &[Commit]) -> HashSet<String> {
+    commits
+        .iter()
+        .flat_map(|commit| commit.contributors())
+        .map(identity)
+        .collect()
+}
+
+/// People are told apart by email address, after `.mailmap` has been applied.
+fn identity(person: &Person) -> String {
+    person.email.to_lowercase()
+}
+
+fn commit_data(
+    commit: &Commit,
+    emojis: &[Emoji],
+    forge: Option<&Forge>,
+    config: &ChangelogConfig,
+) -> CommitData {
+    let intention = commit
+        .emoji
+        .as_ref()
+        .and_then(|emoji| emojis.iter().find(|e| e.matches(emoji)))
+        .map(|emoji| emoji.name.clone());
+
+    let mut trailers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for line in commit.body.lines() {
+        if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim();
+            if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                trailers
+                    .entry(key.to_lowercase())
+                    .or_default()
+                    .push(value.trim().to_string());
+            }
+        }
+    }
+
+    let refs = trailers
+        .get("refs")
+        .into_iter()
+        .flatten()
+        .flat_map(|value| value.split([',', ' ']))
+        .map(|id| id.trim().trim_start_matches('#'))
+        .filter(|id| !id.is_empty())
+        .map(|id| Reference {
+            id: id.to_string(),
+            url: forge
+                .filter(|_| id.chars().all(|c| c.is_ascii_digit()))
+                .map(|forge| forge.issue(id)),
+        })
+        .collect();
+
+    CommitData {
+        hash: commit.hash.clone(),
+        short_hash: commit.short_hash().to_string(),
+        subject: commit.subject.clone(),
+        emoji: commit.emoji.clone(),
+        intention,
+        scope: commit.scope.clone(),
+        message: commit.message.clone(),
+        body: commit.body.clone(),
+        bump: commit.semver.as_ref().map(|bump| bump.to_string()),
+        section: Section::of(commit, config),
+        author: commit.author.clone(),
+        co_authors: commit.co_authors.clone(),
+        trailers,
+        url: forge.map(|forge| forge.commit(&commit.hash)),
+        refs,
+    }
+}
+
+/// Replaces issue references such as `#123` with Markdown links to the issue.
+fn link_issues(text: &str, forge: &Forge) -> String {
+    let issue = regex::Regex::new(r"(^|[^\w&\[/])#(\d+)\b").unwrap();
+
+    issue
+        .replace_all(text, |captures: &regex::Captures| {
+            format!(
+                "{}[#{}]({})",
+                &captures[1],
+                &captures[2],
+                forge.issue(&captures[2])
+            )
+        })
+        .to_string()
+}
+
+/// Groups the commits by a key, in order of first appearance, leaving out those without one.
+fn group_by(commits: &[CommitData], key: impl Fn(&CommitData) -> Option<String>) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+
+    for commit in commits {
+        let Some(title) = key(commit) else {
+            continue;
+        };
+
+        match groups.iter_mut().find(|group| group.title == title) {
+            Some(group) => group.commits.push(commit.clone()),
+            None => groups.push(Group {
+                title,
+                commits: vec![commit.clone()],
+            }),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(author: &str, body: &str) -> Commit {
+        let mut commit = Commit::from_log("🐛 fix".to_string(), body.to_string());
+        commit.author = Person::parse(author);
+        commit
+    }
+
+    #[test]
+    fn counts_each_contributor_once_per_commit() {
+        let commits = [
+            commit(
+                "Alice <alice@example.com>",
+                "Co-authored-by: Bob <bob@example.com>\nCo-authored-by: Alice <ALICE@example.com>",
+            ),
+            commit("Bob <bob@example.com>", ""),
+        ];
+
+        let contributors = contributors(&commits, &HashSet::new())
+            .into_iter()
+            .map(|c| (c.name, c.commits))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            contributors,
+            [("Alice".to_string(), 1), ("Bob".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn highlights_first_time_contributors() {
+        let commits = [
+            commit("Alice <alice@example.com>", ""),
+            commit("Bob <bob@example.com>", ""),
+        ];
+        let contributed = HashSet::from(["alice@example.com".to_string()]);
+
+        let first_time = contributors(&commits, &contributed)
+            .into_iter()
+            .map(|c| (c.name, c.first_time))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            first_time,
+            [("Alice".to_string(), false), ("Bob".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn everyone_is_new_in_the_first_release() {
+        let commits = [commit("Alice <alice@example.com>", "")];
+
+        assert!(contributors(&commits, &HashSet::new())[0].first_time);
+    }
+}
@@ -1,8 +1,36 @@
+use std::{cell::RefCell, path::PathBuf, process::Command};
+
 use anyhow::anyhow;
 use anyhow::Result;
 
+thread_local! {
+    /// Where commands run on this thread, the process working directory if unset.
+    static DIRECTORY: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with the commands on this thread running in `dir`, leaving the process alone.
+#[cfg(test)]
+pub fn in_directory<T>(dir: &std::path::Path, f: impl FnOnce() -> T) -> T {
+    let previous = DIRECTORY.replace(Some(dir.to_path_buf()));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    DIRECTORY.set(previous);
+
+    result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn command(program: &str) -> Command {
+    let mut command = Command::new(program);
+    DIRECTORY.with_borrow(|dir| {
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+    });
+
+    command
+}
+
 pub fn execute(program: &str, args: Vec<&str>) -> Result<String> {
-    let output = std::process::Command::new(program)
+    let output = command(program)
         .args(&args)
         .output()
         .map_err(|e| anyhow!(e))?;
@@ -20,3 +48,21 @@ pub fn execute(program: &str, args: Vec<&str>) -> Result<String> {
         )))
     }
 }
+
+/// Runs a command with its output streamed straight to the terminal.
+pub fn stream(program: &str, args: Vec<&str>) -> Result<()> {
+    let status = command(program)
+        .args(&args)
+        .status()
+        .map_err(|e| anyhow!(e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("Exited with {}", status).context(format!(
+            "Failed to execute command: {} {}",
+            program,
+            args.join(" ")
+        )))
+    }
+}
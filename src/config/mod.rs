@@ -0,0 +1,339 @@
+use crate::cmd::execute;
+use crate::forge::ForgeKind;
+use crate::git::{sign::Signing, tag::TagFormat};
+use anyhow::Context;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE: &str = "committer.toml";
+
+/// Settings read from `committer.toml` in the root of the repository.
+/// Every setting is optional; a missing file means the defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub lint: LintConfig,
+    pub signing: SigningConfig,
+    pub tag: TagConfig,
+    pub prerelease: PrereleaseConfig,
+    pub version: VersionConfig,
+    pub changelog: ChangelogConfig,
+    pub forge: ForgeConfig,
+    /// The version files to update on release, defaults to the Cargo workspace
+    #[serde(rename = "updater")]
+    pub updaters: Vec<UpdaterConfig>,
+    /// Independently released packages, configured as `[[package]]` tables.
+    /// Without any, the whole repository is released as one version
+    #[serde(rename = "package")]
+    pub packages: Vec<PackageConfig>,
+}
+
+/// A package in a monorepo, with its own versions, tags and changelogs.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackageConfig {
+    pub name: String,
+    /// The directory the version files are updated in
+    #[serde(deserialize_with = "directory")]
+    pub dir: PathBuf,
+    /// Globs of the files that belong to the package, defaults to everything in `dir`
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// The release tag name, with `{name}` and `{version}` placeholders
+    #[serde(default = "default_package_tag_format")]
+    pub tag_format: String,
+}
+
+/// Reads a directory the way git writes paths, without a leading `./` or trailing `/`,
+/// so the globs built from it match.
+fn directory<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    let dir = String::deserialize(deserializer)?;
+    let dir = dir.trim_end_matches('/');
+    let dir = dir.strip_prefix("./").unwrap_or(dir);
+
+    Ok(PathBuf::from(if dir.is_empty() { "." } else { dir }))
+}
+
+fn default_package_tag_format() -> String {
+    "{name}@v{version}".to_string()
+}
+
+impl PackageConfig {
+    pub fn format(&self) -> anyhow::Result<TagFormat> {
+        TagFormat::new(&self.tag_format, Some(self.name.clone()))
+    }
+
+    /// Whether any of the files belongs to the package.
+    pub fn contains(&self, files: &[String]) -> anyhow::Result<bool> {
+        let paths = if !self.paths.is_empty() {
+            self.paths.clone()
+        } else if self.dir == Path::new(".") {
+            vec!["**".to_string()]
+        } else {
+            vec![format!("{}/**", self.dir.display())]
+        };
+
+        let patterns = paths
+            .iter()
+            .map(|path| glob::Pattern::new(path))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(files
+            .iter()
+            .any(|file| patterns.iter().any(|pattern| pattern.matches(file))))
+    }
+}
+
+/// A version file, configured as an `[[updater]]` table.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdaterConfig {
+    pub kind: UpdaterKind,
+    /// The file to update, or the workspace directory for `cargo`; defaults to the usual name
+    pub path: Option<PathBuf>,
+    /// For `regex`, a pattern whose `version` group captures the version
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdaterKind {
+    Cargo,
+    PackageJson,
+    Pyproject,
+    HelmChart,
+    Nix,
+    Plain,
+    Regex,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ChangelogConfig {
+    /// The changelog file in the released directory, which is updated on release once it exists
+    pub file: PathBuf,
+    /// A Jinja template to render the release notes with, instead of the default layout
+    pub template: Option<PathBuf>,
+    /// Sections for commits of certain intentions, e.g. `zap` under "Performance"
+    #[serde(rename = "section")]
+    pub sections: Vec<SectionConfig>,
+    /// List the commits that don't change the version, under "Maintenance" unless a section claims them
+    pub maintenance: bool,
+    /// Group the commits of each section by scope
+    pub group_by_scope: bool,
+    /// Thank the authors and co-authors of each release, highlighting first-time contributors
+    pub contributors: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SectionConfig {
+    pub title: String,
+    /// Intention names like `bug`, or the emojis themselves
+    pub intentions: Vec<String>,
+}
+
+impl Default for ChangelogConfig {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from("CHANGELOG.md"),
+            template: None,
+            sections: Vec::new(),
+            maintenance: false,
+            group_by_scope: false,
+            contributors: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ForgeConfig {
+    /// The remote whose URL identifies the forge, defaults to `origin`
+    pub remote: Option<String>,
+    /// The forge software, for self-hosted instances that aren't recognized by their host name
+    pub kind: Option<ForgeKind>,
+    /// The web URL of the repository, instead of the one derived from the remote
+    pub url: Option<String>,
+    /// Link template for commits, with `{url}` and `{hash}` placeholders
+    pub commit_url: Option<String>,
+    /// Link template for comparing releases, with `{url}`, `{from}` and `{to}` placeholders
+    pub compare_url: Option<String>,
+    /// Link template for issues, with `{url}` and `{issue}` placeholders
+    pub issue_url: Option<String>,
+    /// Link template for release tags, with `{url}` and `{tag}` placeholders
+    pub tag_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct VersionConfig {
+    /// While the major version is 0, breaking changes bump the minor version
+    /// and new features bump the patch version
+    pub initial_development: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PrereleaseConfig {
+    /// Maps branch names to pre-release channels, e.g. `next = "rc"`
+    pub branches: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TagConfig {
+    /// The release tag name, with `{version}` and optionally `{package}` placeholders
+    pub format: String,
+    /// The value for `{package}`, defaults to the name in `Cargo.toml`
+    pub package: Option<String>,
+    /// Commit the bumped manifests before tagging, so the tag points at the new version
+    pub release_commit: bool,
+}
+
+impl Default for TagConfig {
+    fn default() -> Self {
+        Self {
+            format: "v{version}".to_string(),
+            package: None,
+            release_commit: true,
+        }
+    }
+}
+
+impl TagConfig {
+    pub fn format(&self) -> anyhow::Result<TagFormat> {
+        let package = match &self.package {
+            Some(package) => Some(package.clone()),
+            None if self.format.contains("{package}") || self.format.contains("{name}") => {
+                crate::updater::cargo::package_name()?
+            }
+            None => None,
+        };
+
+        TagFormat::new(&self.format, package)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LintConfig {
+    /// The subject must start with a known intention emoji
+    pub intention: bool,
+    /// The maximum number of characters in the subject, if any
+    pub max_subject_length: Option<usize>,
+    /// The subject must not end with a period
+    pub no_trailing_period: bool,
+    /// The body must contain a `semver:` trailer
+    pub semver_trailer: bool,
+    /// The `semver:` trailer must match the intention
+    pub semver_consistency: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SigningConfig {
+    /// Sign commits created by committer
+    pub commits: bool,
+    /// Sign release tags, which makes them annotated
+    pub tags: bool,
+    /// The key to sign with, instead of `user.signingkey`
+    pub key: Option<String>,
+    /// The signature format, instead of `gpg.format`
+    pub format: Option<String>,
+    /// Refuse to use a release tag without a valid signature as the base version
+    pub strict: bool,
+}
+
+impl SigningConfig {
+    pub fn commits(&self) -> Signing {
+        Signing {
+            enabled: self.commits,
+            key: self.key.clone(),
+            format: self.format.clone(),
+        }
+    }
+
+    pub fn tags(&self) -> Signing {
+        Signing {
+            enabled: self.tags,
+            key: self.key.clone(),
+            format: self.format.clone(),
+        }
+    }
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            intention: true,
+            max_subject_length: Some(72),
+            no_trailing_period: true,
+            semver_trailer: true,
+            semver_consistency: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Self> {
+        let path = path()?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+fn path() -> anyhow::Result<PathBuf> {
+    let root = execute("git", vec!["rev-parse", "--show-toplevel"])?;
+
+    Ok(PathBuf::from(root.trim()).join(CONFIG_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(dir: &str) -> PackageConfig {
+        let config: Config = toml::from_str(&format!(
+            "[[package]]\nname = \"core\"\ndir = \"{}\"\n",
+            dir
+        ))
+        .unwrap();
+
+        config.packages.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn normalizes_package_directories() {
+        assert_eq!(package("crates/core/").dir, Path::new("crates/core"));
+        assert_eq!(package("./crates/core").dir, Path::new("crates/core"));
+        assert_eq!(package("./").dir, Path::new("."));
+    }
+
+    #[test]
+    fn packages_contain_the_files_in_their_directory() {
+        for dir in ["crates/core", "crates/core/", "./crates/core"] {
+            let package = package(dir);
+
+            assert!(package
+                .contains(&["crates/core/src/lib.rs".to_string()])
+                .unwrap());
+            assert!(!package
+                .contains(&["crates/app/src/main.rs".to_string()])
+                .unwrap());
+        }
+
+        assert!(package("./")
+            .contains(&["src/main.rs".to_string()])
+            .unwrap());
+    }
+}
@@ -18,6 +18,20 @@ impl Emoji {
     pub fn all() -> Vec<Emoji> {
         serde_json::from_str(EMOJI_JSON).unwrap()
     }
+
+    /// Whether the given emoji is this one, ignoring variation selectors.
+    pub fn matches(&self, emoji: &str) -> bool {
+        self.emoji.trim_end_matches('\u{fe0f}') == emoji.trim_end_matches('\u{fe0f}')
+    }
+
+    pub fn semver_trailer(&self) -> &'static str {
+        match self.semver {
+            Some(SemVer::Major) => "semver: major",
+            Some(SemVer::Minor) => "semver: minor",
+            Some(SemVer::Patch) => "semver: patch",
+            None => "semver: chore",
+        }
+    }
 }
 
 impl Display for Emoji {
@@ -0,0 +1,288 @@
+use serde::Deserialize;
+
+use crate::config::ForgeConfig;
+
+/// The code hosting software behind a remote, which decides how its web URLs look.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForgeKind {
+    #[serde(rename = "github")]
+    GitHub,
+    #[serde(rename = "gitlab")]
+    GitLab,
+    Gitea,
+    Bitbucket,
+}
+
+impl ForgeKind {
+    /// Recognizes the well-known hosts. Self-hosted instances are configured with `kind`.
+    fn detect(host: &str) -> Option<Self> {
+        match host.to_lowercase().as_str() {
+            "github.com" => Some(ForgeKind::GitHub),
+            "gitlab.com" => Some(ForgeKind::GitLab),
+            "bitbucket.org" => Some(ForgeKind::Bitbucket),
+            "codeberg.org" | "gitea.com" | "gitea.io" => Some(ForgeKind::Gitea),
+            _ => None,
+        }
+    }
+
+    /// The commit, compare, issue and tag URL templates.
+    fn templates(&self) -> [&'static str; 4] {
+        match self {
+            ForgeKind::GitHub | ForgeKind::Gitea => [
+                "{url}/commit/{hash}",
+                "{url}/compare/{from}...{to}",
+                "{url}/issues/{issue}",
+                "{url}/releases/tag/{tag}",
+            ],
+            ForgeKind::GitLab => [
+                "{url}/-/commit/{hash}",
+                "{url}/-/compare/{from}...{to}",
+                "{url}/-/issues/{issue}",
+                "{url}/-/tags/{tag}",
+            ],
+            ForgeKind::Bitbucket => [
+                "{url}/commits/{hash}",
+                "{url}/branches/compare/{to}%0D{from}",
+                "{url}/issues/{issue}",
+                "{url}/src/{tag}",
+            ],
+        }
+    }
+}
+
+/// Where the repository is hosted, and how to link to its commits, compare views, issues and tags.
+#[derive(Debug, Clone)]
+pub struct Forge {
+    pub kind: Option<ForgeKind>,
+    /// The web URL of the repository, e.g. `https://github.com/owner/repo`
+    pub url: String,
+    commit: String,
+    compare: String,
+    issue: String,
+    tag: String,
+}
+
+impl Forge {
+    /// Describes the forge of the configured remote, or `None` if the remote isn't hosted anywhere.
+    pub fn detect(config: &ForgeConfig) -> anyhow::Result<Option<Self>> {
+        let remote = config.remote.as_deref().unwrap_or("origin");
+
+        let parsed = crate::git::remote::url(remote)?.as_deref().and_then(parse);
+
+        let url = match (&config.url, &parsed) {
+            (Some(url), _) => url.trim_end_matches('/').to_string(),
+            (None, Some((host, path))) => format!("https://{}/{}", host, path),
+            (None, None) => return Ok(None),
+        };
+
+        let kind = config.kind.or_else(|| {
+            let (host, _) = parsed.or_else(|| parse(&url))?;
+            ForgeKind::detect(&host)
+        });
+
+        // Unrecognized forges most likely follow GitHub's URL scheme, as Gitea and others do
+        let [commit, compare, issue, tag] = kind.unwrap_or(ForgeKind::GitHub).templates();
+        let template = |custom: &Option<String>, default: &str| {
+            custom.clone().unwrap_or_else(|| default.to_string())
+        };
+
+        Ok(Some(Self {
+            commit: template(&config.commit_url, commit),
+            compare: template(&config.compare_url, compare),
+            issue: template(&config.issue_url, issue),
+            tag: template(&config.tag_url, tag),
+            kind,
+            url,
+        }))
+    }
+
+    pub fn commit(&self, hash: &str) -> String {
+        self.expand(&self.commit, &[("{hash}", hash)])
+    }
+
+    pub fn compare(&self, from: &str, to: &str) -> String {
+        self.expand(&self.compare, &[("{from}", from), ("{to}", to)])
+    }
+
+    pub fn issue(&self, issue: &str) -> String {
+        self.expand(&self.issue, &[("{issue}", issue)])
+    }
+
+    pub fn tag(&self, tag: &str) -> String {
+        self.expand(&self.tag, &[("{tag}", tag)])
+    }
+
+    fn expand(&self, template: &str, values: &[(&str, &str)]) -> String {
+        values
+            .iter()
+            .fold(template.replace("{url}", &self.url), |url, (key, value)| {
+                url.replace(key, value)
+            })
+    }
+}
+
+/// Splits a remote URL into host and repository path, e.g. `github.com` and `owner/repo`
+/// for both `git@github.com:owner/repo.git` and `https://github.com/owner/repo`.
+/// Local paths and other transports aren't hosted anywhere, so they return `None`.
+fn parse(remote: &str) -> Option<(String, String)> {
+    let remote = remote.trim().trim_end_matches('/');
+    let remote = remote.strip_suffix(".git").unwrap_or(remote);
+
+    let (host, path) = if let Some(rest) = remote
+        .strip_prefix("https://")
+        .or_else(|| remote.strip_prefix("http://"))
+        .or_else(|| remote.strip_prefix("ssh://"))
+    {
+        rest.split_once('/')?
+    } else if remote.contains("://") {
+        return None;
+    } else {
+        // scp-like syntax, `user@host:path`
+        remote.split_once(':')?
+    };
+
+    let host = host.rsplit('@').next()?;
+    // SSH ports don't apply to the web interface
+    let host = match remote.starts_with("ssh://") {
+        true => host.split(':').next()?,
+        false => host,
+    };
+
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    Some((host.to_string(), path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosted(host: &str, path: &str) -> Option<(String, String)> {
+        Some((host.to_string(), path.to_string()))
+    }
+
+    fn forge(kind: ForgeKind, url: &str) -> Forge {
+        let [commit, compare, issue, tag] = kind.templates();
+
+        Forge {
+            kind: Some(kind),
+            url: url.to_string(),
+            commit: commit.to_string(),
+            compare: compare.to_string(),
+            issue: issue.to_string(),
+            tag: tag.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_remotes() {
+        let repo = hosted("github.com", "owner/repo");
+        assert_eq!(parse("git@github.com:owner/repo.git"), repo);
+        assert_eq!(parse("https://github.com/owner/repo.git"), repo);
+        assert_eq!(parse("https://github.com/owner/repo/"), repo);
+        assert_eq!(parse("https://user@github.com/owner/repo"), repo);
+        assert_eq!(parse("ssh://git@github.com/owner/repo.git"), repo);
+        assert_eq!(parse("ssh://git@github.com:22/owner/repo.git"), repo);
+
+        assert_eq!(
+            parse("git@gitlab.com:group/subgroup/repo.git"),
+            hosted("gitlab.com", "group/subgroup/repo")
+        );
+        assert_eq!(
+            parse("https://bitbucket.org/owner/repo"),
+            hosted("bitbucket.org", "owner/repo")
+        );
+        assert_eq!(
+            parse("ssh://git@git.example.com:2222/team/repo.git"),
+            hosted("git.example.com", "team/repo")
+        );
+        assert_eq!(
+            parse("http://git.example.com:3000/team/repo"),
+            hosted("git.example.com:3000", "team/repo")
+        );
+    }
+
+    #[test]
+    fn ignores_local_remotes() {
+        assert_eq!(parse("/srv/git/repo.git"), None);
+        assert_eq!(parse("file:///srv/git/repo.git"), None);
+        assert_eq!(parse("../repo"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn detects_well_known_hosts() {
+        assert_eq!(ForgeKind::detect("github.com"), Some(ForgeKind::GitHub));
+        assert_eq!(ForgeKind::detect("GitHub.com"), Some(ForgeKind::GitHub));
+        assert_eq!(ForgeKind::detect("gitlab.com"), Some(ForgeKind::GitLab));
+        assert_eq!(
+            ForgeKind::detect("bitbucket.org"),
+            Some(ForgeKind::Bitbucket)
+        );
+        assert_eq!(ForgeKind::detect("codeberg.org"), Some(ForgeKind::Gitea));
+
+        // Self-hosted instances and mirrors are configured explicitly
+        assert_eq!(ForgeKind::detect("github.example-mirror.org"), None);
+        assert_eq!(ForgeKind::detect("gitlab.example.com"), None);
+        assert_eq!(ForgeKind::detect("git.example.com"), None);
+    }
+
+    #[test]
+    fn links_to_github() {
+        let forge = forge(ForgeKind::GitHub, "https://github.com/owner/repo");
+
+        assert_eq!(
+            forge.commit("abc123"),
+            "https://github.com/owner/repo/commit/abc123"
+        );
+        assert_eq!(
+            forge.compare("v1.0.0", "v1.1.0"),
+            "https://github.com/owner/repo/compare/v1.0.0...v1.1.0"
+        );
+        assert_eq!(forge.issue("42"), "https://github.com/owner/repo/issues/42");
+        assert_eq!(
+            forge.tag("v1.1.0"),
+            "https://github.com/owner/repo/releases/tag/v1.1.0"
+        );
+    }
+
+    #[test]
+    fn links_to_gitlab() {
+        let forge = forge(ForgeKind::GitLab, "https://gitlab.com/group/repo");
+
+        assert_eq!(
+            forge.commit("abc123"),
+            "https://gitlab.com/group/repo/-/commit/abc123"
+        );
+        assert_eq!(
+            forge.compare("v1.0.0", "HEAD"),
+            "https://gitlab.com/group/repo/-/compare/v1.0.0...HEAD"
+        );
+        assert_eq!(forge.issue("7"), "https://gitlab.com/group/repo/-/issues/7");
+        assert_eq!(
+            forge.tag("v1.0.0"),
+            "https://gitlab.com/group/repo/-/tags/v1.0.0"
+        );
+    }
+
+    #[test]
+    fn links_to_bitbucket() {
+        let forge = forge(ForgeKind::Bitbucket, "https://bitbucket.org/owner/repo");
+
+        assert_eq!(
+            forge.commit("abc123"),
+            "https://bitbucket.org/owner/repo/commits/abc123"
+        );
+        assert_eq!(
+            forge.compare("v1.0.0", "v1.1.0"),
+            "https://bitbucket.org/owner/repo/branches/compare/v1.1.0%0Dv1.0.0"
+        );
+        assert_eq!(
+            forge.tag("v1.1.0"),
+            "https://bitbucket.org/owner/repo/src/v1.1.0"
+        );
+    }
+}
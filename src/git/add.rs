@@ -0,0 +1,9 @@
+use crate::cmd::execute;
+use anyhow::Result;
+
+pub fn add(paths: &[&str]) -> Result<String> {
+    let mut args = vec!["add", "--"];
+    args.extend(paths);
+
+    execute("git", args)
+}
@@ -0,0 +1,61 @@
+use crate::cmd::execute;
+use anyhow::Result;
+
+pub fn current() -> Result<String> {
+    let branch = execute("git", vec!["rev-parse", "--abbrev-ref", "HEAD"])?;
+
+    Ok(branch.trim().to_string())
+}
+
+/// Returns the upstream of the current branch, e.g. `origin/main`.
+pub fn upstream() -> Result<Option<String>> {
+    let upstream = execute(
+        "git",
+        vec!["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+    )
+    .ok()
+    .map(|u| u.trim().to_string())
+    .filter(|u| !u.is_empty());
+
+    Ok(upstream)
+}
+
+/// Returns the remote the current branch tracks, if any.
+pub fn upstream_remote() -> Result<Option<String>> {
+    let branch = current()?;
+    let key = format!("branch.{}.remote", branch);
+
+    let remote = execute("git", vec!["config", "--get", &key])
+        .ok()
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+
+    Ok(remote)
+}
+
+/// Returns the branch on the remote the current branch tracks, e.g. `refs/heads/main`.
+pub fn upstream_branch() -> Result<Option<String>> {
+    let branch = current()?;
+    let key = format!("branch.{}.merge", branch);
+
+    let merge = execute("git", vec!["config", "--get", &key])
+        .ok()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+
+    Ok(merge)
+}
+
+/// Returns the commit the remote-tracking branch of the upstream points at.
+pub fn upstream_hash() -> Result<String> {
+    let hash = execute("git", vec!["rev-parse", "@{u}"])?;
+
+    Ok(hash.trim().to_string())
+}
+
+/// Whether the given revision is contained in any remote-tracking branch.
+pub fn is_pushed(rev: &str) -> Result<bool> {
+    let branches = execute("git", vec!["branch", "-r", "--contains", rev])?;
+
+    Ok(!branches.trim().is_empty())
+}
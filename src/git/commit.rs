@@ -1,6 +1,75 @@
-use crate::cmd::execute;
+use crate::cmd::{execute, stream};
+use crate::git::sign::Signing;
 use anyhow::Result;
+use std::fmt::{Display, Formatter};
 
-pub fn commit(message: String) -> Result<String> {
-    execute("git", vec!["commit", "-m", &message])
+pub fn commit(message: String, signing: &Signing) -> Result<String> {
+    git_commit(vec!["-m", &message], signing)
+}
+
+/// Commits only the given paths, leaving anything else in the index alone.
+pub fn commit_paths(message: String, paths: &[&str], signing: &Signing) -> Result<String> {
+    let mut extra = vec!["-m", &message, "--"];
+    extra.extend(paths);
+
+    git_commit(extra, signing)
+}
+
+pub fn amend(message: String, signing: &Signing) -> Result<String> {
+    git_commit(vec!["--amend", "-m", &message], signing)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FixupKind {
+    Fixup,
+    Squash,
+    Amend,
+}
+
+/// Creates a `fixup!`, `squash!` or `amend!` commit for the given target.
+pub fn fixup(kind: FixupKind, target: &str, signing: &Signing) -> Result<()> {
+    match kind {
+        FixupKind::Fixup => {
+            let fixup = format!("--fixup={}", target);
+            git_commit(vec![&fixup], signing).map(|_| ())
+        }
+        FixupKind::Squash => {
+            let squash = format!("--squash={}", target);
+            git_commit(vec![&squash, "--no-edit"], signing).map(|_| ())
+        }
+        // Opens the editor to reword the target, so it needs the terminal
+        FixupKind::Amend => {
+            let amend = format!("--fixup=amend:{}", target);
+            let config_args = signing.config_args();
+            let commit_args = signing.commit_args();
+
+            let mut args: Vec<&str> = config_args.iter().map(|a| a.as_str()).collect();
+            args.extend(["commit", &amend]);
+            args.extend(commit_args.iter().map(|a| a.as_str()));
+
+            stream("git", args)
+        }
+    }
+}
+
+fn git_commit(extra: Vec<&str>, signing: &Signing) -> Result<String> {
+    let config_args = signing.config_args();
+    let commit_args = signing.commit_args();
+
+    let mut args: Vec<&str> = config_args.iter().map(|a| a.as_str()).collect();
+    args.push("commit");
+    args.extend(commit_args.iter().map(|a| a.as_str()));
+    args.extend(extra);
+
+    execute("git", args)
+}
+
+impl Display for FixupKind {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FixupKind::Fixup => write!(f, "fixup! (keep the target message)"),
+            FixupKind::Squash => write!(f, "squash! (combine both messages)"),
+            FixupKind::Amend => write!(f, "amend! (reword the target message)"),
+        }
+    }
 }
@@ -42,6 +42,18 @@ pub fn diff_raw() -> Result<String> {
     execute("git", vec!["--no-pager", "diff", "--cached"])
 }
 
+/// Returns the staged changes relative to the given revision.
+/// Falls back to the empty tree when the revision does not exist, e.g. `HEAD~1` on the root commit.
+pub fn diff_raw_since(rev: &str) -> Result<String> {
+    let base = execute("git", vec!["rev-parse", "--verify", "--quiet", rev])
+        .map(|r| r.trim().to_string())
+        .unwrap_or(EMPTY_TREE.to_string());
+
+    execute("git", vec!["--no-pager", "diff", "--cached", &base])
+}
+
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
 #[derive(Debug)]
 pub struct Change {
     pub kind: ChangeKind,
@@ -0,0 +1,14 @@
+use crate::cmd::execute;
+use anyhow::Result;
+
+/// Writes the index as a tree object, so it can be restored exactly later.
+pub fn write_tree() -> Result<String> {
+    let tree = execute("git", vec!["write-tree"])?;
+
+    Ok(tree.trim().to_string())
+}
+
+/// Replaces the index with a tree written by `write_tree`, leaving the working tree alone.
+pub fn read_tree(tree: &str) -> Result<String> {
+    execute("git", vec!["read-tree", tree])
+}
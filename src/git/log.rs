@@ -1,19 +1,87 @@
 use crate::cmd::execute;
+use crate::emoji::Emoji;
 use anyhow::Result;
 
-pub fn log() -> Result<Vec<Commit>> {
-    Ok(vec![])
+/// Returns the most recent commits reachable from HEAD, newest first.
+pub fn log(limit: usize) -> Result<Vec<Commit>> {
+    let limit = format!("--max-count={}", limit);
+
+    let output = execute(
+        "git",
+        vec!["--no-pager", "log", &limit, "--name-only", LOG_FORMAT],
+    )?;
+
+    Ok(parse_log(&output))
+}
+
+/// Returns the most recent commits of HEAD that aren't on any remote-tracking branch, newest first.
+pub fn unpushed(limit: usize) -> Result<Vec<Commit>> {
+    let limit = format!("--max-count={}", limit);
+
+    let output = execute(
+        "git",
+        vec![
+            "--no-pager",
+            "log",
+            &limit,
+            "--name-only",
+            LOG_FORMAT,
+            "HEAD",
+            "--not",
+            "--remotes",
+        ],
+    )?;
+
+    Ok(parse_log(&output))
+}
+
+/// Returns the commits in the given revision range, e.g. `origin/main..HEAD`, newest first.
+pub fn range(range: &str) -> Result<Vec<Commit>> {
+    let output = execute(
+        "git",
+        vec!["--no-pager", "log", range, "--name-only", LOG_FORMAT],
+    )?;
+
+    Ok(parse_log(&output))
+}
+
+/// Maps the co-authors to their canonical identities, as git already does for authors.
+fn resolve_co_authors(commits: &mut [Commit]) {
+    let mut co_authors: Vec<Person> = Vec::new();
+    for co_author in commits.iter().flat_map(|commit| &commit.co_authors) {
+        if !co_authors.contains(co_author) {
+            co_authors.push(co_author.clone());
+        }
+    }
+
+    // Only the credits depend on it, so the identities are kept as written if git can't help
+    let Ok(resolved) = crate::git::mailmap::check(&co_authors) else {
+        return;
+    };
+    let canonical = co_authors
+        .into_iter()
+        .zip(resolved)
+        .collect::<std::collections::HashMap<_, _>>();
+
+    for co_author in commits.iter_mut().flat_map(|commit| &mut commit.co_authors) {
+        if let Some(person) = canonical.get(co_author) {
+            *co_author = person.clone();
+        }
+    }
 }
 
 /// Returns all commits since the given tag, with their subject and body.
-/// If tag is "v0.0.0", returns all commits from the root.
-pub fn commits_since(tag: &str) -> Result<Vec<Commit>> {
-    let range = if tag == "v0.0.0" {
-        // Get root commit and log from there
-        let root = execute("git", vec!["rev-list", "--max-parents=0", "HEAD"])?;
-        format!("{}..HEAD", root.trim())
-    } else {
-        format!("{}..HEAD", tag)
+/// Without a tag, returns all commits from the root.
+pub fn commits_since(tag: Option<&str>) -> Result<Vec<Commit>> {
+    commits_between(tag, "HEAD")
+}
+
+/// Returns the commits reachable from `to` but not from `from`, or all commits up to `to`.
+/// Merge commits are left out, as the changes they bring in are listed by their own commits.
+pub fn commits_between(from: Option<&str>, to: &str) -> Result<Vec<Commit>> {
+    let range = match from {
+        Some(from) => format!("{}..{}", from, to),
+        None => to.to_string(),
     };
 
     let output = execute(
@@ -21,36 +89,152 @@ pub fn commits_since(tag: &str) -> Result<Vec<Commit>> {
         vec![
             "--no-pager",
             "log",
+            "--no-merges",
             &range,
-            "--pretty=format:%H%x00%s%x00%b%x00",
+            "--name-only",
+            LOG_FORMAT,
         ],
     )?;
 
-    let commits = output
-        .split("\x00\n")
+    let mut commits = parse_log(&output);
+    resolve_co_authors(&mut commits);
+
+    Ok(commits)
+}
+
+/// Each entry starts with a record separator, followed by the hash, author name and email
+/// after `.mailmap`, subject and body separated by NUL bytes. `--name-only` appends the touched files after the last NUL.
+const LOG_FORMAT: &str = "--pretty=format:%x1e%H%x00%aN%x00%aE%x00%s%x00%b%x00";
+
+fn parse_log(output: &str) -> Vec<Commit> {
+    output
+        .split('\x1e')
         .filter(|s| !s.trim().is_empty())
         .filter_map(|entry| {
-            let parts: Vec<&str> = entry.splitn(3, '\x00').collect();
-            if parts.len() >= 2 {
-                let subject = parts[1].trim().to_string();
-                let body = parts.get(2).unwrap_or(&"").trim().to_string();
-                Some(Commit::from_log(subject, body))
+            let parts: Vec<&str> = entry.splitn(6, '\x00').collect();
+            if parts.len() >= 4 {
+                let hash = parts[0].trim().to_string();
+                let author = Person {
+                    name: parts[1].trim().to_string(),
+                    email: parts[2].trim().to_string(),
+                };
+                let subject = parts[3].trim().to_string();
+                let body = parts.get(4).unwrap_or(&"").trim().to_string();
+                let files = parts
+                    .get(5)
+                    .unwrap_or(&"")
+                    .lines()
+                    .map(|l| l.trim())
+                    .filter(|l| !l.is_empty())
+                    .map(|l| l.to_string())
+                    .collect();
+
+                let mut commit = Commit::from_log(subject, body);
+                commit.hash = hash;
+                commit.author = Some(author);
+                commit.files = files;
+                Some(commit)
             } else {
                 None
             }
         })
-        .collect();
+        .collect()
+}
 
-    Ok(commits)
+/// Returns the trimmed values of the trailers with the given key, which is case-insensitive.
+fn trailers(body: &str, key: &str) -> Vec<String> {
+    let prefix = format!("{}:", key.to_lowercase());
+
+    body.lines()
+        .map(|line| line.trim())
+        .filter(|line| line.to_lowercase().starts_with(&prefix))
+        .map(|line| line[prefix.len()..].trim().to_string())
+        .collect()
+}
+
+/// Splits a known intention emoji, with its variation selector if any, off the start of a subject.
+fn split_emoji(subject: &str) -> (Option<String>, &str) {
+    static EMOJIS: std::sync::OnceLock<Vec<Emoji>> = std::sync::OnceLock::new();
+
+    let emoji = EMOJIS
+        .get_or_init(Emoji::all)
+        .iter()
+        .map(|emoji| emoji.emoji.trim_end_matches('\u{fe0f}'))
+        .filter(|emoji| subject.starts_with(emoji))
+        .max_by_key(|emoji| emoji.len());
+
+    match emoji {
+        Some(emoji) => {
+            let rest = &subject[emoji.len()..];
+            let rest = rest.strip_prefix('\u{fe0f}').unwrap_or(rest);
+            let length = subject.len() - rest.len();
+
+            (Some(subject[..length].to_string()), rest)
+        }
+        None => (None, subject),
+    }
+}
+
+/// Returns the hash of the commit HEAD points at.
+pub fn head_hash() -> Result<String> {
+    let hash = execute("git", vec!["rev-parse", "HEAD"])?;
+
+    Ok(hash.trim().to_string())
+}
+
+/// Returns the commit HEAD points at.
+pub fn head() -> Result<Commit> {
+    let output = execute(
+        "git",
+        vec!["--no-pager", "log", "-1", "--pretty=format:%s%x00%b"],
+    )?;
+
+    let (subject, body) = output.split_once('\x00').unwrap_or((&output, ""));
+
+    Ok(Commit::from_log(
+        subject.trim().to_string(),
+        body.trim().to_string(),
+    ))
 }
 
 #[derive(Debug)]
 pub struct Commit {
+    pub subject: String,
     pub emoji: Option<String>,
     pub scope: Option<String>,
     pub message: String,
     pub body: String,
     pub semver: Option<SemVerBump>,
+    pub hash: String,
+    pub author: Option<Person>,
+    /// The people credited in `Co-authored-by` trailers
+    pub co_authors: Vec<Person>,
+    pub files: Vec<String>,
+}
+
+/// A commit author, as recorded by git.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct Person {
+    pub name: String,
+    pub email: String,
+}
+
+impl Person {
+    /// Parses an identity like `Jane Doe <jane@example.com>`.
+    pub fn parse(identity: &str) -> Option<Self> {
+        let (name, email) = identity.trim().strip_suffix('>')?.rsplit_once('<')?;
+
+        Some(Self {
+            name: name.trim().to_string(),
+            email: email.trim().to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for Person {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -60,51 +244,158 @@ pub enum SemVerBump {
     Patch,
 }
 
+impl std::fmt::Display for SemVerBump {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SemVerBump::Major => write!(f, "major"),
+            SemVerBump::Minor => write!(f, "minor"),
+            SemVerBump::Patch => write!(f, "patch"),
+        }
+    }
+}
+
 impl Commit {
     pub fn from_log(subject: String, body: String) -> Self {
-        let semver = if body.to_lowercase().contains("semver: major") {
-            Some(SemVerBump::Major)
-        } else if body.to_lowercase().contains("semver: minor") {
-            Some(SemVerBump::Minor)
-        } else if body.to_lowercase().contains("semver: patch") {
-            Some(SemVerBump::Patch)
-        } else {
-            None
+        // The same parsing as `trailers`, so a commit with a trailer always gets its bump
+        let semver = match trailers(&body, "semver").first().map(|v| v.to_lowercase()) {
+            Some(value) if value == "major" => Some(SemVerBump::Major),
+            Some(value) if value == "minor" => Some(SemVerBump::Minor),
+            Some(value) if value == "patch" => Some(SemVerBump::Patch),
+            _ => None,
         };
 
-        let commit_regex =
-            regex::Regex::new(r"^([^\w\s:()]+)?\s*(?:\(?([^\)]+)\)?\s*:)?\s*([\s\w]*)$").unwrap();
-
-        let captures = commit_regex.captures(&subject);
-
-        match captures {
-            Some(captures) => {
-                let emoji = captures.get(1).map(|m| m.as_str().trim().to_string());
-                let scope = captures.get(2).map(|m| m.as_str().trim().to_string());
-                let message = captures
-                    .get(3)
-                    .map(|m| m.as_str().trim().to_string())
-                    .unwrap_or_default();
-
-                Self {
-                    emoji,
-                    scope,
-                    message,
-                    body,
-                    semver,
-                }
-            }
-            None => Self {
-                emoji: None,
-                scope: None,
-                message: subject,
-                body,
-                semver,
-            },
+        let co_authors = body
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .filter(|(key, _)| key.trim().eq_ignore_ascii_case("co-authored-by"))
+            .filter_map(|(_, identity)| Person::parse(identity))
+            .collect::<Vec<_>>();
+
+        let (emoji, rest) = split_emoji(&subject);
+
+        let scope_regex = regex::Regex::new(r"^\s*(?:\(([^)]+)\)\s*:)?\s*(.*)$").unwrap();
+        let captures = scope_regex.captures(rest);
+
+        let scope = captures
+            .as_ref()
+            .and_then(|captures| captures.get(1))
+            .map(|m| m.as_str().trim().to_string());
+        let message = captures
+            .as_ref()
+            .and_then(|captures| captures.get(2))
+            .map(|m| m.as_str().trim().to_string())
+            .unwrap_or_else(|| rest.trim().to_string());
+
+        Self {
+            subject: subject.clone(),
+            emoji,
+            scope,
+            message,
+            body,
+            semver,
+            hash: String::new(),
+            author: None,
+            co_authors,
+            files: vec![],
         }
     }
 
     pub fn new(message: impl Into<String>) -> Self {
         Self::from_log(message.into(), String::new())
     }
+
+    /// Returns the values of all trailers with the given key, e.g. `semver` or `Co-authored-by`.
+    pub fn trailers(&self, key: &str) -> Vec<String> {
+        trailers(&self.body, key)
+    }
+
+    /// Returns the author followed by the co-authors.
+    pub fn contributors(&self) -> impl Iterator<Item = &Person> {
+        self.author.iter().chain(&self.co_authors)
+    }
+
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_emoji_scope_and_message() {
+        let commit = Commit::new("🐛 (parser): fix off-by-one in `parse`");
+
+        assert_eq!(commit.emoji.as_deref(), Some("🐛"));
+        assert_eq!(commit.scope.as_deref(), Some("parser"));
+        assert_eq!(commit.message, "fix off-by-one in `parse`");
+    }
+
+    #[test]
+    fn keeps_punctuation_in_the_message() {
+        let commit = Commit::new("🔖 release 1.4.0");
+
+        assert_eq!(commit.emoji.as_deref(), Some("🔖"));
+        assert_eq!(commit.scope, None);
+        assert_eq!(commit.message, "release 1.4.0");
+    }
+
+    #[test]
+    fn keeps_the_variation_selector_with_the_emoji() {
+        let commit = Commit::new("⚡️ (api): faster lookups");
+
+        assert_eq!(commit.emoji.as_deref(), Some("⚡️"));
+        assert_eq!(commit.scope.as_deref(), Some("api"));
+        assert_eq!(commit.message, "faster lookups");
+    }
+
+    #[test]
+    fn parses_the_semver_trailer_once() {
+        let tight = Commit::from_log("✨ add".to_string(), "semver:minor".to_string());
+        let loose = Commit::from_log("✨ add".to_string(), "  Semver:  Minor  ".to_string());
+        let prose = Commit::from_log("✨ add".to_string(), "Mentions semver: major.".to_string());
+
+        assert_eq!(tight.semver, Some(SemVerBump::Minor));
+        assert_eq!(loose.semver, Some(SemVerBump::Minor));
+        assert_eq!(prose.semver, None);
+        assert!(prose.trailers("semver").is_empty());
+    }
+
+    #[test]
+    fn parses_identities() {
+        assert_eq!(
+            Person::parse(" Jane Q. Doe <jane@example.com> "),
+            Some(Person {
+                name: "Jane Q. Doe".to_string(),
+                email: "jane@example.com".to_string(),
+            })
+        );
+        assert_eq!(Person::parse("jane@example.com"), None);
+    }
+
+    #[test]
+    fn extracts_co_authors() {
+        let commit = Commit::from_log(
+            "✨ pair on it".to_string(),
+            "Details.\n\nsemver: minor\nCo-authored-by: Bob <bob@example.com>\nco-authored-by: Carol <carol@example.com>\nCo-authored-by: nobody".to_string(),
+        );
+
+        let names = commit
+            .co_authors
+            .iter()
+            .map(|person| person.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["Bob", "Carol"]);
+    }
+
+    #[test]
+    fn leaves_subjects_without_an_intention_alone() {
+        let commit = Commit::new("Update README.md");
+
+        assert_eq!(commit.emoji, None);
+        assert_eq!(commit.scope, None);
+        assert_eq!(commit.message, "Update README.md");
+    }
 }
@@ -0,0 +1,27 @@
+use crate::cmd::execute;
+use anyhow::Result;
+
+use super::log::Person;
+
+/// Returns the canonical identities of the given people according to `.mailmap`, in the same order.
+pub fn check(people: &[Person]) -> Result<Vec<Person>> {
+    if people.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let contacts = people
+        .iter()
+        .map(|person| person.to_string())
+        .collect::<Vec<_>>();
+
+    let mut args = vec!["check-mailmap"];
+    args.extend(contacts.iter().map(|contact| contact.as_str()));
+
+    let output = execute("git", args)?;
+
+    Ok(output
+        .lines()
+        .zip(people)
+        .map(|(line, person)| Person::parse(line).unwrap_or_else(|| person.clone()))
+        .collect())
+}
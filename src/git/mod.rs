@@ -1,7 +1,16 @@
+pub mod add;
+pub mod branch;
 pub mod commit;
 pub mod diff;
+pub mod index;
 pub mod log;
+pub mod mailmap;
 pub mod pull;
 pub mod push;
+pub mod rebase;
+pub mod remote;
+pub mod reset;
+pub mod sign;
+pub mod stash;
 pub mod status;
 pub mod tag;
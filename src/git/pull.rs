@@ -1,6 +1,30 @@
 use crate::cmd::execute;
 use anyhow::Result;
+use std::fmt::{Display, Formatter};
 
-pub fn pull() -> Result<String> {
-    execute("git", vec!["pull", "--ff-only"])
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PullStrategy {
+    FastForward,
+    Rebase,
+    Merge,
+}
+
+pub fn pull(strategy: PullStrategy) -> Result<String> {
+    let flag = match strategy {
+        PullStrategy::FastForward => "--ff-only",
+        PullStrategy::Rebase => "--rebase",
+        PullStrategy::Merge => "--no-rebase",
+    };
+
+    execute("git", vec!["pull", flag])
+}
+
+impl Display for PullStrategy {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PullStrategy::FastForward => write!(f, "fast-forward"),
+            PullStrategy::Rebase => write!(f, "rebase"),
+            PullStrategy::Merge => write!(f, "merge"),
+        }
+    }
 }
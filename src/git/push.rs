@@ -1,6 +1,29 @@
-use crate::cmd::execute;
+use crate::cmd::stream;
 use anyhow::Result;
 
-pub fn push() -> Result<String> {
-    execute("git", vec!["push"])
+pub fn push() -> Result<()> {
+    stream("git", vec!["push"])
+}
+
+/// Overwrites a remote branch, but only if it still points at `expected`.
+pub fn force_push(remote: &str, branch: &str, expected: &str) -> Result<()> {
+    let lease = format!("--force-with-lease={}:{}", branch, expected);
+    let refspec = format!("HEAD:{}", branch);
+    stream("git", vec!["push", &lease, remote, &refspec])
+}
+
+pub fn push_upstream(remote: &str, branch: &str) -> Result<()> {
+    stream("git", vec!["push", "--set-upstream", remote, branch])
+}
+
+/// Pushes the branch and the tag together, so either both or neither end up on the remote.
+pub fn push_release(remote: &str, branch: &str, tag: &str) -> Result<()> {
+    let branch = format!("HEAD:refs/heads/{}", branch);
+    let tag = format!("refs/tags/{}", tag);
+    stream("git", vec!["push", "--atomic", remote, &branch, &tag])
+}
+
+pub fn push_tag(remote: &str, tag: &str) -> Result<()> {
+    let refspec = format!("refs/tags/{}", tag);
+    stream("git", vec!["push", remote, &refspec])
 }
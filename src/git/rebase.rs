@@ -0,0 +1,32 @@
+use crate::cmd::{execute, stream};
+use crate::git::sign::Signing;
+use anyhow::Result;
+
+/// Folds `fixup!`, `squash!` and `amend!` commits into their targets without
+/// opening the todo list. The oldest target must be given.
+pub fn autosquash(oldest_target: &str, signing: &Signing) -> Result<()> {
+    let parent = format!("{}~1", oldest_target);
+    let has_parent = execute("git", vec!["rev-parse", "--verify", "--quiet", &parent]).is_ok();
+
+    let config_args = signing.config_args();
+    let commit_args = signing.commit_args();
+
+    let mut args: Vec<&str> = config_args.iter().map(|a| a.as_str()).collect();
+    args.extend([
+        "-c",
+        "sequence.editor=:",
+        "rebase",
+        "--interactive",
+        "--autosquash",
+        "--autostash",
+    ]);
+    args.extend(commit_args.iter().map(|a| a.as_str()));
+
+    if has_parent {
+        args.push(&parent);
+    } else {
+        args.push("--root");
+    }
+
+    stream("git", args)
+}
@@ -0,0 +1,16 @@
+use crate::cmd::execute;
+use anyhow::Result;
+
+pub fn remotes() -> Result<Vec<String>> {
+    let output = execute("git", vec!["remote"])?;
+
+    Ok(output.lines().map(|l| l.trim().to_string()).collect())
+}
+
+/// Returns the fetch URL of a remote, if the remote exists.
+pub fn url(remote: &str) -> Result<Option<String>> {
+    match execute("git", vec!["remote", "get-url", remote]) {
+        Ok(url) => Ok(Some(url.trim().to_string())),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,7 @@
+use crate::cmd::execute;
+use anyhow::Result;
+
+/// Moves HEAD back to the given revision, keeping the changes staged.
+pub fn soft(rev: &str) -> Result<String> {
+    execute("git", vec!["reset", "--soft", rev])
+}
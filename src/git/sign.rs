@@ -0,0 +1,70 @@
+use crate::cmd::execute;
+use anyhow::{anyhow, Result};
+
+/// How commits or tags should be signed. When disabled, git's own
+/// `commit.gpgSign` and `tag.gpgSign` settings still apply.
+#[derive(Debug, Clone, Default)]
+pub struct Signing {
+    pub enabled: bool,
+    /// Overrides `user.signingkey`
+    pub key: Option<String>,
+    /// Overrides `gpg.format`, e.g. `openpgp`, `ssh` or `x509`
+    pub format: Option<String>,
+}
+
+impl Signing {
+    /// Config overrides to pass to git before the subcommand.
+    pub fn config_args(&self) -> Vec<String> {
+        match (&self.format, self.enabled) {
+            (Some(format), true) => vec!["-c".to_string(), format!("gpg.format={}", format)],
+            _ => vec![],
+        }
+    }
+
+    pub fn commit_args(&self) -> Vec<String> {
+        match (&self.key, self.enabled) {
+            (_, false) => vec![],
+            (Some(key), true) => vec![format!("--gpg-sign={}", key)],
+            (None, true) => vec!["--gpg-sign".to_string()],
+        }
+    }
+
+    pub fn tag_args(&self) -> Vec<String> {
+        match (&self.key, self.enabled) {
+            (_, false) => vec![],
+            (Some(key), true) => vec!["--local-user".to_string(), key.to_string()],
+            (None, true) => vec!["--sign".to_string()],
+        }
+    }
+
+    /// Fails early with a clear message when git would not know which key to use.
+    pub fn check(&self) -> Result<()> {
+        if !self.enabled || self.key.is_some() {
+            return Ok(());
+        }
+
+        let format = match &self.format {
+            Some(format) => format.clone(),
+            None => execute("git", vec!["config", "--get", "gpg.format"])
+                .map(|f| f.trim().to_string())
+                .unwrap_or_default(),
+        };
+
+        let has_key = execute("git", vec!["config", "--get", "user.signingkey"]).is_ok();
+
+        if format == "ssh" && !has_key {
+            return Err(anyhow!(
+                "SSH signing requires a key. Set `user.signingkey` or `signing.key` in committer.toml"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies the signature of the given tag.
+pub fn verify_tag(tag: &str) -> Result<()> {
+    execute("git", vec!["verify-tag", tag])
+        .map(|_| ())
+        .map_err(|e| e.context(format!("Tag {} does not have a valid signature", tag)))
+}
@@ -0,0 +1,24 @@
+use crate::cmd::execute;
+use anyhow::Result;
+
+/// Stashes all local changes, including untracked files.
+/// Returns whether anything was stashed.
+pub fn push() -> Result<bool> {
+    let output = execute(
+        "git",
+        vec![
+            "stash",
+            "push",
+            "--include-untracked",
+            "-m",
+            "committer: autostash",
+        ],
+    )?;
+
+    Ok(!output.contains("No local changes to save"))
+}
+
+/// Restores the most recent stash, including what was staged.
+pub fn pop() -> Result<String> {
+    execute("git", vec!["stash", "pop", "--index"])
+}
@@ -7,6 +7,10 @@ pub struct Status {
     pub message: String,
 }
 
+pub fn is_repository() -> bool {
+    execute("git", vec!["rev-parse", "--is-inside-work-tree"]).is_ok()
+}
+
 pub fn status() -> Result<Status> {
     execute("git", vec!["remote", "update"])?;
     let status = execute(
@@ -41,3 +45,41 @@ pub fn status() -> Result<Status> {
         message: status,
     })
 }
+
+pub fn is_ignored(path: &str) -> bool {
+    execute("git", vec!["check-ignore", "--quiet", path]).is_ok()
+}
+
+/// Returns those of the given paths that have uncommitted changes, staged or not.
+pub fn dirty(paths: &[&str]) -> Result<Vec<String>> {
+    if paths.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut args = vec![
+        "--no-pager",
+        "status",
+        "--porcelain",
+        "--untracked-files=no",
+        "--",
+    ];
+    args.extend(paths);
+
+    let output = execute("git", args)?;
+
+    Ok(output
+        .lines()
+        .filter_map(|l| l.get(3..))
+        .map(|l| l.to_string())
+        .collect())
+}
+
+/// Returns the paths of all files with unresolved conflicts.
+pub fn conflicts() -> Result<Vec<String>> {
+    let output = execute(
+        "git",
+        vec!["--no-pager", "diff", "--name-only", "--diff-filter=U"],
+    )?;
+
+    Ok(output.lines().map(|l| l.to_string()).collect())
+}
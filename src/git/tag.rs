@@ -1,26 +1,288 @@
 use crate::cmd::execute;
+use crate::git::sign::Signing;
 
-pub fn latest() -> anyhow::Result<String> {
-    execute("git", vec!["fetch", "--tags"])?;
+/// A template for release tag names, e.g. `v{version}`, `{package}@{version}` or `release-{version}`.
+/// The same template is used to create tags and to recognize them. `{name}` is the same as `{package}`.
+#[derive(Debug, Clone)]
+pub struct TagFormat {
+    template: String,
+    package: Option<String>,
+    regex: regex::Regex,
+}
+
+impl TagFormat {
+    pub fn new(template: &str, package: Option<String>) -> anyhow::Result<Self> {
+        if !template.contains("{version}") {
+            return Err(anyhow::anyhow!(
+                "Tag format '{}' must contain {{version}}",
+                template
+            ));
+        }
+
+        let template = template.to_string();
+        let normalized = template.replace("{name}", "{package}");
+
+        if normalized.contains("{package}") && package.is_none() {
+            return Err(anyhow::anyhow!(
+                "Tag format '{}' uses {{package}}, but no package name is known",
+                template
+            ));
+        }
+
+        let pattern = regex::escape(&normalized)
+            .replace(
+                r"\{version\}",
+                r"(?P<version>[0-9]+\.[0-9]+\.[0-9]+(?:-[0-9A-Za-z.-]+)?)",
+            )
+            .replace(
+                r"\{package\}",
+                &regex::escape(package.as_deref().unwrap_or_default()),
+            );
+        let regex = regex::Regex::new(&format!("^{}$", pattern))?;
+
+        Ok(Self {
+            template,
+            package,
+            regex,
+        })
+    }
+
+    pub fn format(&self, version: &semver::Version) -> String {
+        self.template
+            .replace("{version}", &version.to_string())
+            .replace("{package}", self.package.as_deref().unwrap_or_default())
+            .replace("{name}", self.package.as_deref().unwrap_or_default())
+    }
+
+    /// Returns the version of a tag that matches this format.
+    pub fn parse(&self, tag: &str) -> Option<semver::Version> {
+        let captures = self.regex.captures(tag.trim())?;
+
+        semver::Version::parse(&captures["version"]).ok()
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReleaseTag {
+    pub name: String,
+    pub version: semver::Version,
+    /// The hash of the commit the tag points at
+    pub commit: String,
+    /// The day the tag was created, e.g. `2024-01-31`
+    pub date: String,
+}
+
+impl ReleaseTag {
+    pub fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(7)]
+    }
+}
 
-    // Find the latest stable (non-draft) semver tag, matching workflow logic
-    let tags = execute("git", vec!["tag", "--sort=-v:refname"]).unwrap_or_default();
+/// Returns the latest stable release tag that is an ancestor of HEAD, so tags on unmerged
+/// branches, forks and pre-releases are never used. In strict mode, its signature must be valid.
+/// The tags are fetched from the remote first, unless `fetch` is false.
+pub fn latest(format: &TagFormat, strict: bool, fetch: bool) -> anyhow::Result<Option<ReleaseTag>> {
+    if fetch {
+        execute("git", vec!["fetch", "--tags"])?;
+    }
 
-    let tag = tags
+    let latest = find_latest(format, |version| version.pre.is_empty())?;
+
+    if let (true, Some(latest)) = (strict, &latest) {
+        crate::git::sign::verify_tag(&latest.name)?;
+    }
+
+    Ok(latest)
+}
+
+/// Returns the latest pre-release of the given version on the given channel,
+/// e.g. `1.3.0-rc.2` for version `1.3.0` and channel `rc`.
+pub fn latest_prerelease(
+    format: &TagFormat,
+    version: &semver::Version,
+    channel: &str,
+) -> anyhow::Result<Option<ReleaseTag>> {
+    let prefix = format!("{}.", channel);
+
+    find_latest(format, |v| {
+        (v.major, v.minor, v.patch) == (version.major, version.minor, version.patch)
+            && v.pre.as_str().starts_with(&prefix)
+    })
+}
+
+fn find_latest(
+    format: &TagFormat,
+    filter: impl Fn(&semver::Version) -> bool,
+) -> anyhow::Result<Option<ReleaseTag>> {
+    Ok(releases(format)?
+        .into_iter()
+        .rfind(|tag| filter(&tag.version)))
+}
+
+/// Returns the release tags, including pre-releases, that are ancestors of HEAD, oldest version first.
+pub fn releases(format: &TagFormat) -> anyhow::Result<Vec<ReleaseTag>> {
+    // `*objectname` is the commit an annotated tag points at, and empty for lightweight tags
+    let tags = execute(
+        "git",
+        vec![
+            "for-each-ref",
+            "--merged",
+            "HEAD",
+            "--format=%(refname:short)%00%(objectname)%00%(*objectname)%00%(creatordate:short)",
+            "refs/tags",
+        ],
+    )
+    .unwrap_or_default();
+
+    let mut releases = tags
         .lines()
-        .find(|line| {
-            let re = regex::Regex::new(r"^v[0-9]+\.[0-9]+\.[0-9]+$").unwrap();
-            re.is_match(line.trim())
+        .filter_map(|line| {
+            let parts = line.split('\x00').collect::<Vec<&str>>();
+            let [name, object, commit, date] = parts.as_slice() else {
+                return None;
+            };
+
+            let version = format.parse(name)?;
+            let commit = if commit.is_empty() { object } else { commit };
+
+            Some(ReleaseTag {
+                name: name.to_string(),
+                version,
+                commit: commit.to_string(),
+                date: date.to_string(),
+            })
         })
-        .unwrap_or("v0.0.0")
-        .trim()
-        .to_string();
+        .collect::<Vec<ReleaseTag>>();
 
-    Ok(tag)
+    releases.sort_by(|a, b| a.version.cmp(&b.version));
+
+    Ok(releases)
 }
 
-pub fn tag(tag: String) -> anyhow::Result<()> {
-    execute("git", vec!["tag", &tag])?;
+pub fn delete(tag: &str) -> anyhow::Result<()> {
+    execute("git", vec!["tag", "--delete", tag])?;
 
     Ok(())
 }
+
+pub fn exists(tag: &str) -> anyhow::Result<bool> {
+    let refname = format!("refs/tags/{}", tag);
+
+    Ok(execute("git", vec!["rev-parse", "--verify", "--quiet", &refname]).is_ok())
+}
+
+/// Returns the tags that look like releases, but don't match any of the configured formats.
+/// These are ignored when determining the latest version.
+pub fn mismatched(formats: &[TagFormat]) -> anyhow::Result<Vec<String>> {
+    let tags = execute("git", vec!["tag"])?;
+    let version = regex::Regex::new(r"[0-9]+\.[0-9]+\.[0-9]+")?;
+
+    Ok(tags
+        .lines()
+        .map(|tag| tag.trim())
+        .filter(|tag| {
+            version.is_match(tag) && formats.iter().all(|format| format.parse(tag).is_none())
+        })
+        .map(|tag| tag.to_string())
+        .collect())
+}
+
+/// Creates an annotated tag on HEAD, with the version as subject and the release notes as body.
+pub fn tag(tag: String, notes: &str, signing: &Signing) -> anyhow::Result<()> {
+    let message = format!("{}\n\n{}", tag, notes.trim());
+
+    let config_args = signing.config_args();
+    let tag_args = signing.tag_args();
+
+    let mut args: Vec<&str> = config_args.iter().map(|a| a.as_str()).collect();
+    args.extend(["tag", "--annotate"]);
+    args.extend(tag_args.iter().map(|a| a.as_str()));
+    // The notes contain markdown headings, which the default cleanup would strip as comments
+    args.extend(["--cleanup=whitespace", "-m", &message, &tag]);
+
+    execute("git", args)?;
+
+    Ok(())
+}
+
+/// Returns the release notes stored in an annotated tag.
+pub fn notes(tag: &str) -> anyhow::Result<String> {
+    let refname = format!("refs/tags/{}", tag);
+
+    let kind = execute(
+        "git",
+        vec!["for-each-ref", "--format=%(objecttype)", &refname],
+    )?;
+    if kind.trim().is_empty() {
+        return Err(anyhow::anyhow!("Tag {} does not exist", tag));
+    }
+
+    // Lightweight tags point straight at a commit and carry no notes
+    if kind.trim() != "tag" {
+        return Ok(String::new());
+    }
+
+    let notes = execute(
+        "git",
+        vec!["for-each-ref", "--format=%(contents:body)", &refname],
+    )?;
+
+    Ok(notes.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> semver::Version {
+        semver::Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn round_trips_versions() {
+        let formats = [
+            TagFormat::new("v{version}", None).unwrap(),
+            TagFormat::new("release-{version}", None).unwrap(),
+            TagFormat::new("{package}@{version}", Some("core".to_string())).unwrap(),
+            TagFormat::new("{name}@v{version}", Some("app".to_string())).unwrap(),
+        ];
+
+        for format in &formats {
+            for v in ["0.1.0", "1.2.3", "2.0.0-rc.1", "1.0.0-beta.2.x-y"] {
+                let tag = format.format(&version(v));
+
+                assert_eq!(format.parse(&tag), Some(version(v)), "{}", tag);
+            }
+        }
+    }
+
+    #[test]
+    fn formats_package_names() {
+        let format = TagFormat::new("{name}@v{version}", Some("app".to_string())).unwrap();
+
+        assert_eq!(format.format(&version("1.2.3")), "app@v1.2.3");
+        assert_eq!(format.template(), "{name}@v{version}");
+    }
+
+    #[test]
+    fn ignores_other_formats_and_packages() {
+        let plain = TagFormat::new("v{version}", None).unwrap();
+        let core = TagFormat::new("{package}@{version}", Some("core".to_string())).unwrap();
+
+        assert_eq!(plain.parse("1.2.3"), None);
+        assert_eq!(plain.parse("v1.2"), None);
+        assert_eq!(plain.parse("av1.2.3"), None);
+        assert_eq!(core.parse("app@1.2.3"), None);
+        assert_eq!(core.parse("core-extra@1.2.3"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(TagFormat::new("release", None).is_err());
+        assert!(TagFormat::new("{package}-{version}", None).is_err());
+    }
+}
@@ -1,68 +1,409 @@
 use std::io::Write;
+use std::path::PathBuf;
 
+use crate::changelog::template::Notes;
+use crate::config::{ChangelogConfig, Config, PackageConfig};
 use crate::git::log::{Commit, SemVerBump};
+use crate::git::tag::ReleaseTag;
 use anyhow::Ok;
 
 pub struct ReleaseInfo {
+    pub tag: String,
+    /// The release the new version is based on, if any
+    pub base: Option<ReleaseTag>,
     pub version: semver::Version,
     pub changelog: String,
+    /// The monorepo package being released, if any
+    pub package: Option<String>,
+    /// The directory whose version files are updated
+    pub dir: PathBuf,
 }
 
-pub fn calculate_new_tag_based_on_commits() -> anyhow::Result<Option<ReleaseInfo>> {
-    let latest_tag = crate::git::tag::latest()?;
+#[derive(Default)]
+pub struct ReleaseOptions {
+    /// Tag a pre-release on this channel, e.g. `rc`, instead of a stable release
+    pub channel: Option<String>,
+    /// Release 1.0.0, ending initial development
+    pub graduate: bool,
+    /// Print the commit range and why each commit bumps the version
+    pub explain: bool,
+    /// Fetch the tags from the remote first, which a dry run skips to leave the refs alone
+    pub fetch: bool,
+    /// Bump this level instead of the one the commits imply
+    pub bump: Option<SemVerBump>,
+    /// Release exactly this version instead of the one the commits imply
+    pub version: Option<semver::Version>,
+}
 
-    let latest_tag_clean = latest_tag.replace('v', "");
-    let latest_tag_clean = if latest_tag_clean.split('.').count() == 2 {
-        format!("{}.0", latest_tag_clean)
-    } else {
-        latest_tag_clean
+impl ReleaseOptions {
+    /// Whether the version is chosen by hand rather than by the commits.
+    fn is_manual(&self) -> bool {
+        self.graduate || self.bump.is_some() || self.version.is_some()
+    }
+}
+
+/// Calculates the next release of the repository, or of a single package when given,
+/// in which case only the commits touching the package's files count.
+pub fn calculate_new_tag_based_on_commits(
+    config: &Config,
+    options: &ReleaseOptions,
+    package: Option<&PackageConfig>,
+) -> anyhow::Result<Option<ReleaseInfo>> {
+    let format = match package {
+        Some(package) => package.format()?,
+        None => config.tag.format()?,
     };
 
-    let latest_version = semver::Version::parse(&latest_tag_clean)?;
+    let latest = crate::git::tag::latest(&format, config.signing.strict, options.fetch)?;
+
+    match &latest {
+        Some(base) => println!("Based on {} at commit {}.", base.name, base.short_commit()),
+        None => {
+            println!("No previous release reachable from HEAD, starting from the first commit.")
+        }
+    }
+
+    let latest_version = latest
+        .as_ref()
+        .map(|base| base.version.clone())
+        .unwrap_or(semver::Version::new(0, 0, 0));
 
-    let commits = crate::git::log::commits_since(&latest_tag)?;
+    let commits = commits_since(latest.as_ref().map(|base| base.name.as_str()), package)?;
 
-    if commits.is_empty() {
+    if options.explain {
+        explain(latest.as_ref(), &commits, config);
+    }
+
+    if commits.is_empty() && !options.is_manual() {
         return Ok(None);
     }
 
-    // Determine the highest bump level and collect commits by type
+    // Determine the highest bump level
     let mut bump: Option<SemVerBump> = None;
-    let mut breaking: Vec<&Commit> = Vec::new();
-    let mut features: Vec<&Commit> = Vec::new();
-    let mut fixes: Vec<&Commit> = Vec::new();
 
     for commit in &commits {
         match &commit.semver {
-            Some(SemVerBump::Major) => {
-                bump = Some(SemVerBump::Major);
-                breaking.push(commit);
+            Some(SemVerBump::Major) => bump = Some(SemVerBump::Major),
+            Some(SemVerBump::Minor) if bump != Some(SemVerBump::Major) => {
+                bump = Some(SemVerBump::Minor)
             }
-            Some(SemVerBump::Minor) => {
-                if bump != Some(SemVerBump::Major) {
-                    bump = Some(SemVerBump::Minor);
-                }
-                features.push(commit);
+            Some(SemVerBump::Patch) if bump.is_none() => bump = Some(SemVerBump::Patch),
+            _ => {}
+        }
+    }
+
+    let computed = bump.map(|bump| {
+        let bump = applied_bump(&bump, &latest_version, config);
+        bump_version(&latest_version, &bump)
+    });
+
+    let would_release = computed
+        .as_ref()
+        .map(|v| v.to_string())
+        .unwrap_or("nothing".to_string());
+
+    let (mut new_version, note) = if options.graduate {
+        if latest_version.major > 0 {
+            return Err(anyhow::anyhow!(
+                "Already at {}, graduating only applies to 0.x versions",
+                latest_version
+            ));
+        }
+
+        (semver::Version::new(1, 0, 0), None)
+    } else if let Some(version) = &options.version {
+        let note = format!(
+            "Version set manually to {}, the commits alone would have released {}.",
+            version, would_release
+        );
+
+        (version.clone(), Some(note))
+    } else if let Some(bump) = &options.bump {
+        let note = format!(
+            "Bumped the {} version manually, the commits alone would have released {}.",
+            bump, would_release
+        );
+
+        (bump_version(&latest_version, bump), Some(note))
+    } else {
+        match computed {
+            Some(version) => (version, None),
+            None => return Ok(None),
+        }
+    };
+
+    if options.is_manual() && new_version <= latest_version {
+        return Err(anyhow::anyhow!(
+            "Refusing to go backwards from {} to {}",
+            latest_version,
+            new_version
+        ));
+    }
+
+    if new_version == latest_version {
+        return Ok(None);
+    }
+
+    if let Some(channel) = &options.channel {
+        let previous = crate::git::tag::latest_prerelease(&format, &new_version, channel)?;
+
+        let number = match &previous {
+            // Nothing new since the previous pre-release
+            Some(previous) if commits_since(Some(&previous.name), package)?.is_empty() => {
+                return Ok(None);
             }
-            Some(SemVerBump::Patch) => {
-                if bump.is_none() {
-                    bump = Some(SemVerBump::Patch);
+            Some(previous) => prerelease_number(&previous.version, channel) + 1,
+            None => 1,
+        };
+
+        new_version.pre = semver::Prerelease::new(&format!("{}.{}", channel, number))?;
+    }
+
+    let tag = format.format(&new_version);
+
+    if crate::git::tag::exists(&tag)? {
+        return Err(anyhow::anyhow!("Tag {} already exists", tag));
+    }
+
+    let changelog = crate::changelog::template::render(
+        &Notes {
+            version: Some(&new_version),
+            tag: Some(&tag),
+            previous: latest.as_ref().map(|base| base.name.as_str()),
+            date: Some(crate::changelog::today()),
+            note,
+            commits: &commits,
+            contributed: None,
+        },
+        config,
+    )?;
+
+    Ok(Some(ReleaseInfo {
+        tag,
+        base: latest,
+        version: new_version,
+        changelog,
+        package: package.map(|package| package.name.clone()),
+        dir: package
+            .map(|package| package.dir.clone())
+            .unwrap_or_else(|| PathBuf::from(".")),
+    }))
+}
+
+/// Returns the commits since a tag, or all commits reachable from HEAD.
+/// For a package, only the commits touching its files are returned.
+pub fn commits_since(
+    tag: Option<&str>,
+    package: Option<&PackageConfig>,
+) -> anyhow::Result<Vec<Commit>> {
+    commits_between(tag, "HEAD", package)
+}
+
+/// Returns the commits reachable from `to` but not from `from`, like [`commits_since`].
+pub fn commits_between(
+    from: Option<&str>,
+    to: &str,
+    package: Option<&PackageConfig>,
+) -> anyhow::Result<Vec<Commit>> {
+    let commits = crate::git::log::commits_between(from, to)?;
+
+    let Some(package) = package else {
+        return Ok(commits);
+    };
+
+    let mut touching = Vec::new();
+    for commit in commits {
+        if package.contains(&commit.files)? {
+            touching.push(commit);
+        }
+    }
+
+    Ok(touching)
+}
+
+/// The part of the release notes a commit is listed in.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Section {
+    Breaking,
+    Features,
+    Fixes,
+    /// Commits that don't change the version, when they are listed
+    Maintenance,
+    /// Commits without a `semver:` trailer, which don't follow the convention
+    Other,
+    /// A section configured for certain intentions, by its title
+    #[serde(untagged)]
+    Custom(String),
+}
+
+impl Section {
+    /// Returns the sections in the order they are listed, with the configured ones after the fixes.
+    pub fn all(config: &ChangelogConfig) -> Vec<Self> {
+        let custom = config
+            .sections
+            .iter()
+            .map(|section| Section::Custom(section.title.clone()));
+
+        let mut sections: Vec<Section> = Vec::new();
+        for section in [Section::Breaking, Section::Features, Section::Fixes]
+            .into_iter()
+            .chain(custom)
+            .chain([Section::Maintenance, Section::Other])
+        {
+            // A configured section may reuse the title of a built-in one, listing both together
+            if !sections.iter().any(|s| s.title() == section.title()) {
+                sections.push(section);
+            }
+        }
+
+        sections
+    }
+
+    /// Returns the section of a commit, or `None` for commits that don't affect the version,
+    /// like `semver: chore`, unless maintenance commits are listed. Commits without a `semver:`
+    /// trailer are always listed under "Other".
+    pub fn of(commit: &Commit, config: &ChangelogConfig) -> Option<Self> {
+        let releasing = commit.semver.is_some();
+        let conventional = releasing || !commit.trailers("semver").is_empty();
+
+        if commit.semver == Some(SemVerBump::Major) {
+            return Some(Section::Breaking);
+        }
+
+        if !conventional {
+            return Some(Section::Other);
+        }
+
+        if !releasing && !config.maintenance {
+            return None;
+        }
+
+        if !config.sections.is_empty() {
+            if let Some(emoji) = &commit.emoji {
+                let name = crate::emoji::Emoji::all()
+                    .into_iter()
+                    .find(|e| e.matches(emoji))
+                    .map(|e| e.name);
+
+                // Intentions are configured by name like `zap`, or by the emoji itself
+                let intends = |intention: &String| {
+                    name.as_ref() == Some(intention)
+                        || emoji.trim_end_matches('\u{fe0f}')
+                            == intention.trim_end_matches('\u{fe0f}')
+                };
+
+                if let Some(section) = config
+                    .sections
+                    .iter()
+                    .find(|section| section.intentions.iter().any(intends))
+                {
+                    return Some(Section::Custom(section.title.clone()));
                 }
-                fixes.push(commit);
             }
-            None => {}
+        }
+
+        match &commit.semver {
+            Some(SemVerBump::Minor) => Some(Section::Features),
+            Some(SemVerBump::Patch) => Some(Section::Fixes),
+            _ => Some(Section::Maintenance),
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        match self {
+            Section::Breaking => "Breaking changes",
+            Section::Features => "New features",
+            Section::Fixes => "Fixes",
+            Section::Maintenance => "Maintenance",
+            Section::Other => "Other",
+            Section::Custom(title) => title,
         }
     }
+}
 
-    let bump = match bump {
-        Some(b) => b,
-        None => return Ok(None),
+/// During initial development, breaking changes only bump the minor version
+/// and new features only bump the patch version.
+fn applied_bump(bump: &SemVerBump, version: &semver::Version, config: &Config) -> SemVerBump {
+    if config.version.initial_development && version.major == 0 {
+        match bump {
+            SemVerBump::Major => SemVerBump::Minor,
+            SemVerBump::Minor | SemVerBump::Patch => SemVerBump::Patch,
+        }
+    } else {
+        bump.clone()
+    }
+}
+
+fn explain(base: Option<&ReleaseTag>, commits: &[Commit], config: &Config) {
+    let version = base
+        .map(|base| base.version.clone())
+        .unwrap_or(semver::Version::new(0, 0, 0));
+
+    let range = match base {
+        Some(base) => format!("{}..HEAD", base.name),
+        None => "HEAD".to_string(),
     };
 
-    // Calculate new version
-    let mut major = latest_version.major;
-    let mut minor = latest_version.minor;
-    let mut patch = latest_version.patch;
+    println!(
+        "Range {} contains {} {}:",
+        range,
+        commits.len(),
+        if commits.len() == 1 {
+            "commit"
+        } else {
+            "commits"
+        }
+    );
+
+    let emojis = crate::emoji::Emoji::all();
+
+    for commit in commits {
+        let applied = commit
+            .semver
+            .as_ref()
+            .map(|bump| applied_bump(bump, &version, config));
+        let bump = applied
+            .as_ref()
+            .map(|bump| bump.to_string())
+            .unwrap_or("none".to_string());
+
+        let reason = match commit.trailers("semver").first() {
+            Some(value) if applied != commit.semver => {
+                format!("semver: {}, reduced during initial development", value)
+            }
+            Some(value) => format!("semver: {}", value),
+            None => {
+                let intention = commit
+                    .emoji
+                    .as_ref()
+                    .and_then(|emoji| emojis.iter().find(|e| e.matches(emoji)));
+
+                match intention {
+                    Some(intention) => format!(
+                        "no semver trailer, {} would imply '{}'",
+                        intention.emoji,
+                        intention.semver_trailer()
+                    ),
+                    None => "no semver trailer".to_string(),
+                }
+            }
+        };
+
+        println!(
+            "  {} {:<5} {} ({})",
+            commit.short_hash(),
+            bump,
+            commit.subject,
+            reason
+        );
+    }
+}
+
+fn bump_version(version: &semver::Version, bump: &SemVerBump) -> semver::Version {
+    let mut major = version.major;
+    let mut minor = version.minor;
+    let mut patch = version.patch;
 
     match bump {
         SemVerBump::Major => {
@@ -79,69 +420,179 @@ pub fn calculate_new_tag_based_on_commits() -> anyhow::Result<Option<ReleaseInfo
         }
     }
 
-    let new_version = semver::Version::new(major, minor, patch);
+    semver::Version::new(major, minor, patch)
+}
 
-    if new_version == latest_version {
-        return Ok(None);
+/// Returns `N` from a pre-release like `1.3.0-rc.N`.
+fn prerelease_number(version: &semver::Version, channel: &str) -> u64 {
+    version
+        .pre
+        .as_str()
+        .strip_prefix(channel)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn set_github_env_var(name: &str, value: &str) -> anyhow::Result<()> {
+    println!("Writing {} to .env/{}", value, name);
+
+    std::fs::create_dir_all(".env")?;
+    let mut file = std::fs::File::create(format!(".env/{}", name))?;
+    file.write_all(value.as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SectionConfig;
+
+    fn commit(subject: &str, trailer: &str) -> Commit {
+        Commit::from_log(subject.to_string(), trailer.to_string())
     }
 
-    // Generate changelog matching workflow format
-    let mut changelog = String::new();
+    fn sections(config: &ChangelogConfig) -> Vec<String> {
+        Section::all(config)
+            .iter()
+            .map(|section| section.title().to_string())
+            .collect()
+    }
 
-    if !breaking.is_empty() {
-        changelog.push_str("### Breaking changes\n");
-        for commit in &breaking {
-            changelog.push_str(&format!("- {}\n", format_commit(commit)));
+    fn performance() -> ChangelogConfig {
+        ChangelogConfig {
+            sections: vec![
+                SectionConfig {
+                    title: "Performance".to_string(),
+                    intentions: vec!["zap".to_string()],
+                },
+                SectionConfig {
+                    title: "Fixes".to_string(),
+                    intentions: vec!["🚑️".to_string()],
+                },
+            ],
+            ..ChangelogConfig::default()
         }
-        changelog.push('\n');
     }
 
-    if !features.is_empty() {
-        changelog.push_str("### New features\n");
-        for commit in &features {
-            changelog.push_str(&format!("- {}\n", format_commit(commit)));
-        }
-        changelog.push('\n');
+    #[test]
+    fn sections_follow_the_bump() {
+        let config = ChangelogConfig::default();
+
+        let of = |subject, trailer| Section::of(&commit(subject, trailer), &config);
+
+        assert_eq!(of("💥 drop v1", "semver: major"), Some(Section::Breaking));
+        assert_eq!(of("✨ add", "semver: minor"), Some(Section::Features));
+        assert_eq!(of("🐛 fix", "semver: patch"), Some(Section::Fixes));
+        assert_eq!(of("📝 docs", "semver: chore"), None);
+        assert_eq!(of("Update README", ""), Some(Section::Other));
     }
 
-    if !fixes.is_empty() {
-        changelog.push_str("### Fixes\n");
-        for commit in &fixes {
-            changelog.push_str(&format!("- {}\n", format_commit(commit)));
-        }
-        changelog.push('\n');
+    #[test]
+    fn maintenance_lists_chores_but_keeps_other() {
+        let config = ChangelogConfig {
+            maintenance: true,
+            ..ChangelogConfig::default()
+        };
+
+        let of = |subject, trailer| Section::of(&commit(subject, trailer), &config);
+
+        assert_eq!(of("📝 docs", "semver: chore"), Some(Section::Maintenance));
+        assert_eq!(of("Update README", ""), Some(Section::Other));
     }
 
-    set_github_env_var("COMMITTER_CHANGELOG", &changelog)?;
+    #[test]
+    fn configured_sections_claim_their_intentions() {
+        let config = performance();
 
-    Ok(Some(ReleaseInfo {
-        version: new_version,
-        changelog,
-    }))
-}
+        let of = |subject, trailer| Section::of(&commit(subject, trailer), &config);
 
-fn format_commit(commit: &Commit) -> String {
-    let emoji = commit
-        .emoji
-        .as_ref()
-        .map(|e| format!("{} ", e))
-        .unwrap_or_default();
+        assert_eq!(
+            of("⚡️ (api): faster", "semver: patch"),
+            Some(Section::Custom("Performance".to_string()))
+        );
+        assert_eq!(
+            of("🚑️ hotfix", "semver: patch"),
+            Some(Section::Custom("Fixes".to_string()))
+        );
+        // Breaking changes stay on top whatever their intention
+        assert_eq!(of("⚡️ new cache", "semver: major"), Some(Section::Breaking));
+    }
 
-    let scope = commit
-        .scope
-        .as_ref()
-        .map(|s| format!("({}): ", s))
-        .unwrap_or_default();
+    #[test]
+    fn configured_sections_come_after_the_fixes() {
+        assert_eq!(
+            sections(&ChangelogConfig::default()),
+            [
+                "Breaking changes",
+                "New features",
+                "Fixes",
+                "Maintenance",
+                "Other"
+            ]
+        );
 
-    format!("{}{}{}", emoji, scope, commit.message)
-}
+        // A configured section with a built-in title is listed once, in the built-in's place
+        assert_eq!(
+            sections(&performance()),
+            [
+                "Breaking changes",
+                "New features",
+                "Fixes",
+                "Performance",
+                "Maintenance",
+                "Other"
+            ]
+        );
+    }
 
-pub fn set_github_env_var(name: &str, value: &str) -> anyhow::Result<()> {
-    println!("Writing {} to .env/{}", value, name);
+    fn version(version: &str) -> semver::Version {
+        semver::Version::parse(version).unwrap()
+    }
 
-    std::fs::create_dir_all(".env")?;
-    let mut file = std::fs::File::create(format!(".env/{}", name))?;
-    file.write_all(value.as_bytes())?;
+    #[test]
+    fn bumps_reset_the_lower_levels() {
+        let current = version("1.2.3");
 
-    Ok(())
+        assert_eq!(bump_version(&current, &SemVerBump::Major), version("2.0.0"));
+        assert_eq!(bump_version(&current, &SemVerBump::Minor), version("1.3.0"));
+        assert_eq!(bump_version(&current, &SemVerBump::Patch), version("1.2.4"));
+    }
+
+    #[test]
+    fn bumps_drop_the_prerelease() {
+        assert_eq!(
+            bump_version(&version("1.2.3-rc.2"), &SemVerBump::Patch),
+            version("1.2.4")
+        );
+    }
+
+    #[test]
+    fn initial_development_reduces_bumps() {
+        let mut config = Config::default();
+        config.version.initial_development = true;
+
+        let applied = |current: &str, bump| applied_bump(&bump, &version(current), &config);
+
+        assert_eq!(applied("0.3.1", SemVerBump::Major), SemVerBump::Minor);
+        assert_eq!(applied("0.3.1", SemVerBump::Minor), SemVerBump::Patch);
+        assert_eq!(applied("0.3.1", SemVerBump::Patch), SemVerBump::Patch);
+        // From 1.0.0 on, bumps apply as they are
+        assert_eq!(applied("1.0.0", SemVerBump::Major), SemVerBump::Major);
+
+        let config = Config::default();
+        assert_eq!(
+            applied_bump(&SemVerBump::Major, &version("0.3.1"), &config),
+            SemVerBump::Major
+        );
+    }
+
+    #[test]
+    fn numbers_prereleases_per_channel() {
+        assert_eq!(prerelease_number(&version("1.3.0-rc.2"), "rc"), 2);
+        assert_eq!(prerelease_number(&version("1.3.0-rc.2"), "beta"), 0);
+        assert_eq!(prerelease_number(&version("1.3.0-rc"), "rc"), 0);
+        assert_eq!(prerelease_number(&version("1.3.0-rc.x"), "rc"), 0);
+    }
 }
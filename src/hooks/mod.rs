@@ -0,0 +1,258 @@
+use crate::cmd::execute;
+use crate::config::{Config, LintConfig};
+use crate::lint::Violation;
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const HOOKS: [&str; 2] = ["prepare-commit-msg", "commit-msg"];
+
+/// Marks hook scripts written by committer, so they can be told apart from user hooks.
+const MARKER: &str = "# Installed by committer";
+
+/// Suffix under which an existing hook is kept, so it can be chained and restored.
+const CHAINED_SUFFIX: &str = "committer-chained";
+
+/// The line `git commit --verbose` puts above the diff; everything below it is discarded.
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+pub fn install() -> Result<()> {
+    let dir = hooks_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    for hook in HOOKS {
+        let path = dir.join(hook);
+        let chained = dir.join(format!("{}.{}", hook, CHAINED_SUFFIX));
+
+        if path.exists() && !is_ours(&path)? {
+            if chained.exists() {
+                return Err(anyhow!(
+                    "Cannot install {}: both {} and {} already exist",
+                    hook,
+                    path.display(),
+                    chained.display()
+                ));
+            }
+
+            fs::rename(&path, &chained)?;
+            println!("Chaining existing {} hook.", hook);
+        }
+
+        fs::write(&path, script(hook))?;
+        make_executable(&path)?;
+        println!("Installed {} hook in {}.", hook, dir.display());
+    }
+
+    Ok(())
+}
+
+pub fn uninstall() -> Result<()> {
+    let dir = hooks_dir()?;
+
+    for hook in HOOKS {
+        let path = dir.join(hook);
+        let chained = dir.join(format!("{}.{}", hook, CHAINED_SUFFIX));
+
+        if !path.exists() || !is_ours(&path)? {
+            println!("No committer {} hook installed.", hook);
+            continue;
+        }
+
+        fs::remove_file(&path)?;
+
+        if chained.exists() {
+            fs::rename(&chained, &path)?;
+            println!("Restored previous {} hook.", hook);
+        } else {
+            println!("Removed {} hook.", hook);
+        }
+    }
+
+    Ok(())
+}
+
+/// Fills the commit message file with a generated message, unless git or the user already provided one.
+pub async fn prepare_commit_msg(file: &str, source: Option<&str>) -> Result<()> {
+    // Messages from -m, -F, merges, squashes and amends are left alone
+    if source.is_some_and(|s| s != "template") {
+        return Ok(());
+    }
+
+    let existing = fs::read_to_string(file)?;
+    if without_diff(&existing)
+        .lines()
+        .any(|l| !l.starts_with('#') && !l.trim().is_empty())
+    {
+        return Ok(());
+    }
+
+    if crate::git::diff::diff(true)?.is_empty() {
+        return Ok(());
+    }
+
+    match crate::generate_message().await {
+        Ok(message) => fs::write(file, format!("{}\n{}", message, existing))?,
+        Err(e) => eprintln!("committer: failed to generate message: {}", e),
+    }
+
+    Ok(())
+}
+
+/// Validates the commit message file. Returns whether the message is valid.
+pub fn commit_msg(file: &str, config: &Config) -> Result<bool> {
+    let message = fs::read_to_string(file)?;
+
+    let violations = check(&message, &config.lint);
+
+    for violation in &violations {
+        eprintln!("committer: {} ({})", violation.message, violation.rule);
+    }
+
+    Ok(violations.is_empty())
+}
+
+/// Lints a commit message as git writes it, ignoring comment lines.
+fn check(message: &str, rules: &LintConfig) -> Vec<Violation> {
+    let message = without_diff(message)
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    let (subject, body) = message.trim().split_once('\n').unwrap_or((&message, ""));
+    let commit =
+        crate::git::log::Commit::from_log(subject.trim().to_string(), body.trim().to_string());
+
+    crate::lint::check(&commit, &crate::emoji::Emoji::all(), rules)
+}
+
+/// Cuts a commit message at the scissors line, leaving out the diff of `git commit --verbose`.
+fn without_diff(message: &str) -> &str {
+    match message.find(&format!("\n{}", SCISSORS)) {
+        Some(end) => &message[..end + 1],
+        None if message.starts_with(SCISSORS) => "",
+        None => message,
+    }
+}
+
+/// Resolves the hooks directory, honoring `core.hooksPath`.
+fn hooks_dir() -> Result<PathBuf> {
+    if let Ok(path) = execute("git", vec!["config", "--get", "core.hooksPath"]) {
+        let path = PathBuf::from(path.trim());
+        if path.is_absolute() {
+            return Ok(path);
+        }
+
+        // Relative paths are resolved against the root of the working tree
+        let root = execute("git", vec!["rev-parse", "--show-toplevel"])?;
+        return Ok(PathBuf::from(root.trim()).join(path));
+    }
+
+    let path = execute("git", vec!["rev-parse", "--git-path", "hooks"])?;
+
+    Ok(PathBuf::from(path.trim()))
+}
+
+fn is_ours(path: &Path) -> Result<bool> {
+    Ok(fs::read_to_string(path)
+        .map(|content| content.contains(MARKER))
+        .unwrap_or(false))
+}
+
+fn script(hook: &str) -> String {
+    format!(
+        r#"#!/bin/sh
+{marker}. Remove with `committer hooks uninstall`.
+
+chained="$(dirname "$0")/{hook}.{suffix}"
+if [ -x "$chained" ]; then
+    "$chained" "$@" || exit $?
+fi
+
+if ! command -v committer >/dev/null 2>&1; then
+    exit 0
+fi
+
+exec committer hook {hook} "$@"
+"#,
+        marker = MARKER,
+        hook = hook,
+        suffix = CHAINED_SUFFIX,
+    )
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(0o755);
+    fs::set_permissions(path, permissions)?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(message: &str) -> Vec<&'static str> {
+        check(message, &LintConfig::default())
+            .iter()
+            .map(|violation| violation.rule)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_release_commits() {
+        assert!(rules("🔖 release 1.4.0\n\nsemver: chore\n").is_empty());
+        assert!(rules("🔖 release core 0.2.0-beta.1\n\nsemver: chore\n").is_empty());
+    }
+
+    #[test]
+    fn accepts_code_and_punctuation() {
+        let message =
+            "🐛 (lint): don't reject `parse`-style names, e.g. foo.bar\n\nsemver: patch\n";
+
+        assert!(rules(message).is_empty());
+    }
+
+    #[test]
+    fn ignores_comments_from_the_editor() {
+        let message = "✨ add hooks\n\nsemver: minor\n# Please enter the commit message for your changes.\n# On branch main\n";
+
+        assert!(rules(message).is_empty());
+    }
+
+    #[test]
+    fn ignores_the_diff_below_the_scissors() {
+        let message = "✨ add hooks\n\nsemver: minor\n# Please enter the commit message for your changes.\n# ------------------------ >8 ------------------------\n# Do not modify or remove the line above.\ndiff --git a/src/main.rs b/src/main.rs\n+semver: major\n+Co-authored-by: nobody\n";
+
+        assert!(rules(message).is_empty());
+        assert_eq!(
+            without_diff(message),
+            "✨ add hooks\n\nsemver: minor\n# Please enter the commit message for your changes.\n"
+        );
+    }
+
+    #[test]
+    fn treats_an_empty_verbose_message_as_empty() {
+        let message =
+            "\n# ------------------------ >8 ------------------------\ndiff --git a/a b/a\n+x\n";
+
+        assert_eq!(without_diff(message), "\n");
+        assert_eq!(without_diff(&message[1..]), "");
+    }
+
+    #[test]
+    fn rejects_messages_without_the_convention() {
+        assert_eq!(rules("wip\n"), vec!["intention", "semver-trailer"]);
+    }
+}
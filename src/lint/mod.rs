@@ -0,0 +1,166 @@
+use serde::Serialize;
+
+use crate::{
+    config::LintConfig,
+    emoji::{Emoji, SemVer},
+    git::log::Commit,
+};
+
+#[derive(Serialize)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Checks a commit against the gitmoji and `semver:` trailer convention.
+pub fn check(commit: &Commit, emojis: &[Emoji], rules: &LintConfig) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if is_exempt(&commit.subject) {
+        return violations;
+    }
+
+    let intention = commit
+        .emoji
+        .as_ref()
+        .and_then(|emoji| emojis.iter().find(|e| e.matches(emoji)));
+
+    if rules.intention && intention.is_none() {
+        violations.push(Violation {
+            rule: "intention",
+            message: format!(
+                "subject does not start with a known intention emoji: '{}'",
+                commit.subject
+            ),
+        });
+    }
+
+    if let Some(max) = rules.max_subject_length {
+        let length = commit.subject.chars().count();
+        if length > max {
+            violations.push(Violation {
+                rule: "max-subject-length",
+                message: format!(
+                    "subject is {} characters long, the maximum is {}",
+                    length, max
+                ),
+            });
+        }
+    }
+
+    if rules.no_trailing_period && commit.subject.trim_end().ends_with('.') {
+        violations.push(Violation {
+            rule: "no-trailing-period",
+            message: "subject ends with a period".to_string(),
+        });
+    }
+
+    let trailer = commit
+        .trailers("semver")
+        .first()
+        .map(|value| value.to_lowercase());
+
+    if rules.semver_trailer {
+        match trailer.as_deref() {
+            None => violations.push(Violation {
+                rule: "semver-trailer",
+                message: "missing a 'semver: major|minor|patch|chore' trailer".to_string(),
+            }),
+            Some("major") | Some("minor") | Some("patch") | Some("chore") => {}
+            Some(other) => violations.push(Violation {
+                rule: "semver-trailer",
+                message: format!("unknown semver trailer value '{}'", other),
+            }),
+        }
+    }
+
+    if let (true, Some(intention), Some(trailer)) =
+        (rules.semver_consistency, intention, trailer.as_deref())
+    {
+        let expected = match intention.semver {
+            Some(SemVer::Major) => "major",
+            Some(SemVer::Minor) => "minor",
+            Some(SemVer::Patch) => "patch",
+            None => "chore",
+        };
+
+        if trailer != expected {
+            violations.push(Violation {
+                rule: "semver-consistency",
+                message: format!(
+                    "'{}' implies 'semver: {}', but the trailer says 'semver: {}'",
+                    intention.emoji, expected, trailer
+                ),
+            });
+        }
+    }
+
+    violations
+}
+
+/// Commits created by git itself, which are not expected to follow the convention.
+fn is_exempt(subject: &str) -> bool {
+    ["fixup! ", "squash! ", "amend! ", "Merge ", "Revert \""]
+        .iter()
+        .any(|prefix| subject.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(commit: &Commit) -> Vec<&'static str> {
+        check(commit, &Emoji::all(), &LintConfig::default())
+            .iter()
+            .map(|violation| violation.rule)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_punctuation_in_the_message() {
+        let commit = Commit::from_log(
+            "🐛 fix off-by-one in `parse`".to_string(),
+            "semver: patch".to_string(),
+        );
+
+        assert!(rules(&commit).is_empty());
+    }
+
+    #[test]
+    fn accepts_a_scope() {
+        let commit = Commit::from_log(
+            "✨ (cli): add --dry-run to tag".to_string(),
+            "semver: minor".to_string(),
+        );
+
+        assert!(rules(&commit).is_empty());
+    }
+
+    #[test]
+    fn reports_a_missing_intention() {
+        let commit = Commit::from_log("fix parsing".to_string(), "semver: patch".to_string());
+
+        assert_eq!(rules(&commit), vec!["intention"]);
+    }
+
+    #[test]
+    fn reports_a_missing_trailer() {
+        let commit = Commit::new("🐛 fix parsing");
+
+        assert_eq!(rules(&commit), vec!["semver-trailer"]);
+    }
+
+    #[test]
+    fn reports_an_inconsistent_trailer() {
+        let commit = Commit::from_log("🐛 fix parsing".to_string(), "semver: minor".to_string());
+
+        assert_eq!(rules(&commit), vec!["semver-consistency"]);
+    }
+
+    #[test]
+    fn exempts_fixups() {
+        let commit = Commit::new("fixup! 🐛 fix parsing");
+
+        assert!(rules(&commit).is_empty());
+    }
+}
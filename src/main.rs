@@ -1,18 +1,27 @@
 use anyhow::Ok;
-use clap::{Parser, Subcommand};
-use emoji::{Emoji, SemVer};
+use changelog::template::Notes;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use config::{Config, PackageConfig};
+use emoji::Emoji;
 use genai::{
     chat::{ChatMessage, ChatRequest},
     Client,
 };
-use git::status::Status;
-use helper::set_github_env_var;
+use git::{log::SemVerBump, pull::PullStrategy, status::Status};
+use helper::{set_github_env_var, ReleaseInfo, ReleaseOptions};
+use prompt::fixup_target::Target;
 
+pub mod changelog;
 pub mod cmd;
+pub mod config;
 pub mod emoji;
+pub mod forge;
 pub mod git;
 pub mod helper;
+pub mod hooks;
+pub mod lint;
 pub mod prompt;
+pub mod release;
 pub mod updater;
 
 #[derive(Parser)]
@@ -20,53 +29,465 @@ pub mod updater;
 struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
+
+    /// Sign commits and tags, using `user.signingkey` and `gpg.format` unless configured otherwise
+    #[arg(long, global = true)]
+    sign: bool,
+
+    /// Require a valid signature on the release tag used as the base version
+    #[arg(long, global = true)]
+    strict_signatures: bool,
 }
 
 #[derive(Subcommand)]
 enum Command {
     /// Create a version tag based on commit history
-    Tag,
+    Tag(TagArgs),
+    /// Maintain CHANGELOG.md
+    Changelog(ChangelogArgs),
+    /// Print the release notes stored in a tag
+    Notes {
+        /// The tag to read, defaults to the latest release
+        tag: Option<String>,
+    },
     /// Generate a commit message without committing (for lazygit integration)
     Generate,
+    /// Rewrite the last commit, including any newly staged changes
+    Amend,
+    /// Create a fixup, squash or amend commit for an earlier commit
+    Fixup {
+        /// Fold the fixup into its target right away with a non-interactive rebase
+        #[arg(long)]
+        autosquash: bool,
+    },
+    /// Manage the git hooks that run committer on plain `git commit`
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+    /// Sync the workspace package versions in Cargo.lock with their manifests
+    Lockfile {
+        /// Only check whether Cargo.lock is in sync, failing if it is not
+        #[arg(long)]
+        check: bool,
+    },
+    /// Check commit messages against the commit convention
+    Lint {
+        /// The revision range to check, defaults to the commits not yet on the upstream
+        range: Option<String>,
+        /// How to print the results
+        #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+        format: OutputFormat,
+    },
+    /// Entry point for the installed git hooks
+    #[command(hide = true)]
+    Hook { name: String, args: Vec<String> },
+}
+
+#[derive(Args)]
+struct TagArgs {
+    /// Push the created tag to the remote
+    #[arg(long)]
+    push: bool,
+    /// Tag a pre-release on the given channel, e.g. `rc` for `1.3.0-rc.1`
+    #[arg(long, value_name = "CHANNEL")]
+    pre: Option<String>,
+    /// Release 1.0.0, ending initial development
+    #[arg(long)]
+    graduate: bool,
+    /// Explain the new version and show what would change, without changing anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Bump this level instead of the one the commits imply
+    #[arg(long, value_enum, conflicts_with_all = ["set_version", "graduate"])]
+    bump: Option<BumpLevel>,
+    /// Release exactly this version instead of the one the commits imply
+    #[arg(long, value_name = "X.Y.Z", conflicts_with_all = ["pre", "graduate"])]
+    set_version: Option<semver::Version>,
+    /// Only release this package, when packages are configured
+    #[arg(long)]
+    package: Option<String>,
+}
+
+#[derive(Args)]
+#[command(group(clap::ArgGroup::new("mode").required(true)))]
+struct ChangelogArgs {
+    /// Write the changes since the latest release to the Unreleased section
+    #[arg(long, group = "mode")]
+    unreleased: bool,
+    /// Print the changelog of every release so far, regenerated from the release tags
+    #[arg(long, group = "mode")]
+    full: bool,
+    /// How to print the full changelog
+    #[arg(long, value_enum, default_value_t = ChangelogFormat::Markdown, requires = "full")]
+    format: ChangelogFormat,
+    /// Only update the changelog of this package, when packages are configured
+    #[arg(long)]
+    package: Option<String>,
+}
+
+#[derive(Clone, ValueEnum)]
+enum ChangelogFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Clone, ValueEnum)]
+enum BumpLevel {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl From<BumpLevel> for SemVerBump {
+    fn from(level: BumpLevel) -> Self {
+        match level {
+            BumpLevel::Major => SemVerBump::Major,
+            BumpLevel::Minor => SemVerBump::Minor,
+            BumpLevel::Patch => SemVerBump::Patch,
+        }
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum HooksAction {
+    /// Install the prepare-commit-msg and commit-msg hooks, chaining existing ones
+    Install,
+    /// Remove the hooks and restore the ones they chained
+    Uninstall,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    if crate::git::status::status().is_err() {
+    if !crate::git::status::is_repository() {
         println!("Not in a git repository.");
         return Ok(());
     }
 
+    let mut config = match crate::config::Config::load() {
+        // A broken committer.toml must not block every commit, so hooks fail open
+        Err(e) if matches!(cli.command, Some(Command::Hook { .. })) => {
+            eprintln!(
+                "committer: skipping the hook, fix committer.toml first: {:#}",
+                e
+            );
+            return Ok(());
+        }
+        config => config?,
+    };
+
+    if cli.sign {
+        config.signing.commits = true;
+        config.signing.tags = true;
+    }
+
+    if cli.strict_signatures {
+        config.signing.strict = true;
+    }
+
     match cli.command {
-        Some(Command::Tag) => tag()?,
+        Some(Command::Tag(args)) => tag(args, &config)?,
+        Some(Command::Changelog(args)) => changelog(args, &config)?,
+        Some(Command::Notes { tag }) => notes(tag, &config)?,
         Some(Command::Generate) => generate().await?,
-        None => commit().await?,
+        Some(Command::Amend) => amend(&config).await?,
+        Some(Command::Fixup { autosquash }) => fixup(autosquash, &config)?,
+        Some(Command::Hooks { action }) => match action {
+            HooksAction::Install => crate::hooks::install()?,
+            HooksAction::Uninstall => crate::hooks::uninstall()?,
+        },
+        Some(Command::Lockfile { check }) => lockfile(check)?,
+        Some(Command::Lint { range, format }) => lint(range, format, &config)?,
+        Some(Command::Hook { name, args }) => hook(&name, &args, &config).await?,
+        None => commit(&config).await?,
     }
 
     Ok(())
 }
 
-fn tag() -> anyhow::Result<()> {
-    if let Some(release) = crate::helper::calculate_new_tag_based_on_commits()? {
-        crate::updater::cargo::set_version(&release.version)?;
-        set_github_env_var("COMMITTER_TAG", &release.version.to_string())?;
-        crate::git::tag::tag(release.version.to_string())?;
-        set_github_env_var("COMMITTER_IS_NEW", "true")?;
-        println!("New version tagged as {}.", release.version);
-    } else {
+fn tag(args: TagArgs, config: &Config) -> anyhow::Result<()> {
+    let signing = config.signing.tags();
+    signing.check()?;
+
+    // Releasing one package, the tags of the others still match their own formats
+    let formats = packages(config, None)?
+        .iter()
+        .map(|package| match package {
+            Some(package) => package.format(),
+            None => config.tag.format(),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mismatched = crate::git::tag::mismatched(&formats)?;
+    if !mismatched.is_empty() {
+        let mut templates = formats
+            .iter()
+            .map(|format| format.template())
+            .collect::<Vec<_>>();
+        templates.dedup();
+
+        println!(
+            "Warning: these tags look like releases, but don't match the tag format '{}', so they are ignored: {}",
+            templates.join("', '"),
+            mismatched.join(", ")
+        );
+    }
+
+    let packages = packages(config, args.package.as_deref())?;
+
+    let channel = match args.pre {
+        Some(channel) => Some(channel),
+        None => config
+            .prerelease
+            .branches
+            .get(&crate::git::branch::current()?)
+            .cloned(),
+    };
+
+    let options = ReleaseOptions {
+        channel,
+        graduate: args.graduate,
+        explain: args.dry_run,
+        fetch: !args.dry_run,
+        bump: args.bump.map(SemVerBump::from),
+        version: args.set_version,
+    };
+
+    if args.dry_run {
+        println!("Dry run, using the local tags without fetching.");
+    }
+
+    let mut releases = Vec::new();
+
+    for package in packages {
+        if let Some(package) = package {
+            println!("\nPackage {}:", package.name);
+        }
+
+        let release = crate::helper::calculate_new_tag_based_on_commits(config, &options, package)?;
+
+        if args.dry_run {
+            match &release {
+                Some(release) => {
+                    println!("\nWould tag {} as {}.", release.version, release.tag);
+                    println!("\nWould modify:");
+                    let updaters = crate::updater::configured(config, &release.dir)?;
+                    for file in crate::release::files(&updaters)? {
+                        println!("  {}", file.display());
+                    }
+                    let changelog = crate::updater::join(&release.dir, &config.changelog.file);
+                    if changelog.exists() {
+                        println!("  {}", changelog.display());
+                    }
+                    println!("\nChangelog:\n{}", release.changelog);
+                }
+                None => println!("\nNo new version to tag."),
+            }
+        }
+
+        releases.extend(release);
+    }
+
+    if args.dry_run {
+        if !releases.is_empty() {
+            println!("\nWould write:");
+            for name in ["COMMITTER_TAG", "COMMITTER_IS_NEW", "COMMITTER_CHANGELOG"] {
+                println!("  .env/{}", name);
+            }
+        }
+
+        return Ok(());
+    }
+
+    if releases.is_empty() {
         set_github_env_var("COMMITTER_TAG", "")?;
         set_github_env_var("COMMITTER_IS_NEW", "false")?;
         println!("No new version to tag.");
+
+        return Ok(());
+    }
+
+    let remote = if args.push {
+        Some(crate::git::branch::upstream_remote()?.unwrap_or("origin".to_string()))
+    } else {
+        None
+    };
+
+    // Packages are released one after the other, so catch what would stop a later one first
+    for release in &releases {
+        crate::release::check(release, config)?;
+    }
+
+    let mut released = Vec::new();
+
+    for release in &releases {
+        if let Err(e) = crate::release::release(release, config, remote.as_deref()) {
+            // The earlier packages stay released, which CI needs to know about
+            if !released.is_empty() {
+                set_release_env_vars(&released)?;
+            }
+            return Err(e);
+        }
+
+        println!("New version tagged as {}.", release.tag);
+        released.push(release);
+    }
+
+    set_release_env_vars(&released)
+}
+
+/// Tells CI which tags were created and what changed in them.
+fn set_release_env_vars(releases: &[&ReleaseInfo]) -> anyhow::Result<()> {
+    // A single release keeps its plain changelog, several get a heading per tag
+    let changelog = match releases {
+        [release] if release.package.is_none() => release.changelog.clone(),
+        releases => releases
+            .iter()
+            .map(|release| format!("## {}\n\n{}", release.tag, release.changelog))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    let tags = releases
+        .iter()
+        .map(|release| release.tag.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    set_github_env_var("COMMITTER_CHANGELOG", &changelog)?;
+    set_github_env_var("COMMITTER_TAG", &tags)?;
+    set_github_env_var("COMMITTER_IS_NEW", "true")?;
+
+    Ok(())
+}
+
+/// Returns the configured packages, or only the named one. Without packages,
+/// the whole repository is a single release, represented by `None`.
+fn packages<'a>(
+    config: &'a Config,
+    name: Option<&str>,
+) -> anyhow::Result<Vec<Option<&'a PackageConfig>>> {
+    if let Some(name) = name {
+        if !config.packages.iter().any(|package| package.name == name) {
+            return Err(anyhow::anyhow!("No package named {} is configured", name));
+        }
+    }
+
+    if config.packages.is_empty() {
+        return Ok(vec![None]);
+    }
+
+    Ok(config
+        .packages
+        .iter()
+        .filter(|package| name.is_none_or(|name| package.name == name))
+        .map(Some)
+        .collect())
+}
+
+fn changelog(args: ChangelogArgs, config: &Config) -> anyhow::Result<()> {
+    if args.full {
+        let package = match packages(config, args.package.as_deref())?.as_slice() {
+            [package] => *package,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Several packages are configured, pass the package to show the changelog of"
+                ))
+            }
+        };
+
+        let format = match package {
+            Some(package) => package.format()?,
+            None => config.tag.format()?,
+        };
+
+        let history = crate::changelog::history(&format, package, config)?;
+
+        match args.format {
+            ChangelogFormat::Markdown => print!("{}", crate::changelog::render(&history, config)?),
+            ChangelogFormat::Json => println!("{}", serde_json::to_string_pretty(&history)?),
+        }
+
+        return Ok(());
+    }
+
+    for package in packages(config, args.package.as_deref())? {
+        let (format, dir) = match package {
+            Some(package) => (package.format()?, package.dir.as_path()),
+            None => (config.tag.format()?, std::path::Path::new(".")),
+        };
+
+        let latest = crate::git::tag::latest(&format, config.signing.strict, true)?;
+        let base = latest.as_ref().map(|base| base.name.as_str());
+
+        let commits = crate::helper::commits_since(base, package)?;
+        let notes = crate::changelog::template::render(
+            &Notes {
+                version: None,
+                tag: None,
+                previous: base,
+                date: None,
+                note: None,
+                commits: &commits,
+                contributed: None,
+            },
+            config,
+        )?;
+
+        let path = crate::updater::join(dir, &config.changelog.file);
+        crate::changelog::unreleased(&path, &notes, base, config)?;
+
+        println!(
+            "Updated the unreleased changes in {} ({} {}).",
+            path.display(),
+            commits.len(),
+            if commits.len() == 1 {
+                "commit"
+            } else {
+                "commits"
+            }
+        );
+    }
+
+    Ok(())
+}
+
+fn notes(tag: Option<String>, config: &Config) -> anyhow::Result<()> {
+    let tag = match tag {
+        Some(tag) => tag,
+        None if !config.packages.is_empty() => {
+            return Err(anyhow::anyhow!(
+                "Several packages are configured, pass the tag to show the notes of"
+            ));
+        }
+        None => crate::git::tag::latest(&config.tag.format()?, config.signing.strict, true)?
+            .map(|base| base.name)
+            .ok_or(anyhow::anyhow!("No release tag found"))?,
+    };
+
+    let notes = crate::git::tag::notes(&tag)?;
+
+    if notes.is_empty() {
+        println!("Tag {} has no release notes.", tag);
+    } else {
+        println!("{}", notes);
     }
 
     Ok(())
 }
 
-async fn commit() -> anyhow::Result<()> {
+async fn commit(config: &Config) -> anyhow::Result<()> {
     let emojis = Emoji::all();
 
+    let signing = config.signing.commits();
+    signing.check()?;
+
     let unstaged_diff = crate::git::diff::diff(false)?;
     let staged_diff = crate::git::diff::diff(true)?;
 
@@ -83,6 +504,16 @@ async fn commit() -> anyhow::Result<()> {
 
     let status: Status = crate::git::status::status()?;
 
+    if !sync(&status)? {
+        return Ok(());
+    }
+
+    let status: Status = if status.commits_behind > 0 {
+        crate::git::status::status()?
+    } else {
+        status
+    };
+
     let diff = crate::git::diff::diff_raw()?;
 
     let suggested_scope = suggest_scope(diff.clone(), &emojis)
@@ -105,20 +536,116 @@ async fn commit() -> anyhow::Result<()> {
         .map(Some)
         .unwrap_or(None);
 
+    let subject = crate::prompt::subject::prompt(&intention, vec![], suggested_message, None)?;
+
+    let subject = &format!("{} {}", intention.emoji, subject);
+
+    let semver = intention.semver_trailer();
+
+    let commented_status = status
+        .message
+        .lines()
+        .map(|l| format!("# {}", l))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let message = &format!("{}\n\n{}\n\n{}", subject, commented_status, semver);
+
+    let message = inquire::Editor::new(subject)
+        .with_help_message("What is the body of the commit?")
+        .with_predefined_text(message)
+        .prompt()?;
+
+    let message = message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    crate::git::commit::commit(message, &signing)?;
+
+    let wants_to_push = crate::prompt::push::prompt()?;
+    if wants_to_push {
+        push()?;
+    }
+
+    Ok(())
+}
+
+async fn amend(config: &Config) -> anyhow::Result<()> {
+    let emojis = Emoji::all();
+
+    let signing = config.signing.commits();
+    signing.check()?;
+
+    let head = crate::git::log::head()?;
+
+    if crate::git::branch::is_pushed("HEAD")? {
+        println!(
+            "The last commit has already been pushed. Amending it rewrites published history."
+        );
+
+        let proceed = inquire::Confirm::new("Amend anyway?")
+            .with_default(false)
+            .prompt()?;
+
+        if !proceed {
+            return Ok(());
+        }
+    }
+
+    let status: Status = crate::git::status::status()?;
+
+    let diff = crate::git::diff::diff_raw_since("HEAD~1")?;
+
+    let current_intention = head
+        .emoji
+        .as_ref()
+        .and_then(|emoji| emojis.iter().position(|e| e.matches(emoji)));
+
+    let suggested_scope = match current_intention {
+        Some(current_intention) => Some(current_intention),
+        None => suggest_scope(diff.clone(), &emojis)
+            .await
+            .map(Some)
+            .unwrap_or(None),
+    };
+
+    let intention = inquire::Select::new("Intention:", emojis)
+        .with_help_message("What is intention behind the commit?")
+        .with_starting_cursor(suggested_scope.unwrap_or(0))
+        .prompt()?;
+
+    let suggested_message = suggest_message(diff.clone())
+        .await
+        .map(Some)
+        .unwrap_or(None);
+
+    let current_subject = head
+        .emoji
+        .as_ref()
+        .and_then(|emoji| head.subject.strip_prefix(emoji.as_str()))
+        .unwrap_or(&head.subject)
+        .trim()
+        .to_string();
+
     let subject = crate::prompt::subject::prompt(
         &intention,
         vec![],
         suggested_message,
+        Some(current_subject),
     )?;
 
     let subject = &format!("{} {}", intention.emoji, subject);
 
-    let semver = match intention.semver {
-        Some(SemVer::Major) => "semver: major".to_string(),
-        Some(SemVer::Minor) => "semver: minor".to_string(),
-        Some(SemVer::Patch) => "semver: patch".to_string(),
-        None => "semver: chore".to_string(),
-    };
+    let semver = intention.semver_trailer();
+
+    let body = head
+        .body
+        .lines()
+        .filter(|line| !line.trim().to_lowercase().starts_with("semver:"))
+        .collect::<Vec<&str>>()
+        .join("\n");
 
     let commented_status = status
         .message
@@ -127,11 +654,21 @@ async fn commit() -> anyhow::Result<()> {
         .collect::<Vec<String>>()
         .join("\n");
 
-    let message = &format!("{}\n\n{}\n\n{}", subject, commented_status, semver);
+    let message = if body.trim().is_empty() {
+        format!("{}\n\n{}\n\n{}", subject, commented_status, semver)
+    } else {
+        format!(
+            "{}\n\n{}\n\n{}\n\n{}",
+            subject,
+            body.trim(),
+            commented_status,
+            semver
+        )
+    };
 
     let message = inquire::Editor::new(subject)
         .with_help_message("What is the body of the commit?")
-        .with_predefined_text(message)
+        .with_predefined_text(&message)
         .prompt()?;
 
     let message = message
@@ -140,43 +677,323 @@ async fn commit() -> anyhow::Result<()> {
         .collect::<Vec<&str>>()
         .join("\n");
 
-    crate::git::commit::commit(message)?;
+    crate::git::commit::amend(message, &signing)?;
 
     let wants_to_push = crate::prompt::push::prompt()?;
     if wants_to_push {
-        crate::git::push::push()?;
+        push()?;
     }
 
     Ok(())
 }
 
-async fn generate() -> anyhow::Result<()> {
-    let emojis = Emoji::all();
+fn fixup(autosquash: bool, config: &Config) -> anyhow::Result<()> {
+    let signing = config.signing.commits();
+    signing.check()?;
+
+    let staged_diff = crate::git::diff::diff(true)?;
+
+    if staged_diff.is_empty() {
+        println!("No changes added to commit. Stage changes first.");
+        return Ok(());
+    }
+
+    // Fixing up a commit that is already on a remote would rewrite published history
+    let commits = crate::git::log::unpushed(20)?;
+
+    if commits.is_empty() {
+        println!("No unpushed commits to fix up, commits on a remote are left alone.");
+        return Ok(());
+    }
+
+    // Rank the commits by how many of the staged files they touched, keeping recent ones first on ties
+    let mut targets = commits
+        .iter()
+        .filter(|commit| !is_fixup(&commit.subject))
+        .map(|commit| Target {
+            commit,
+            shared_files: commit
+                .files
+                .iter()
+                .filter(|file| staged_diff.iter().any(|change| &change.path == *file))
+                .count(),
+        })
+        .collect::<Vec<Target>>();
+    targets.sort_by_key(|target| std::cmp::Reverse(target.shared_files));
+
+    let target = crate::prompt::fixup_target::prompt(targets)?;
+    let kind = crate::prompt::fixup_kind::prompt()?;
+
+    crate::git::commit::fixup(kind, &target.hash, &signing)?;
+
+    if autosquash {
+        crate::git::rebase::autosquash(&target.hash, &signing)?;
+    }
+
+    Ok(())
+}
+
+fn is_fixup(subject: &str) -> bool {
+    ["fixup! ", "squash! ", "amend! "]
+        .iter()
+        .any(|prefix| subject.starts_with(prefix))
+}
+
+/// Pushes the current branch, setting an upstream or force pushing when needed.
+fn push() -> anyhow::Result<()> {
+    if crate::git::branch::upstream()?.is_none() {
+        let branch = crate::git::branch::current()?;
+        let remotes = crate::git::remote::remotes()?;
+
+        let remote = match remotes.len() {
+            0 => {
+                println!("No remote configured. Add one with `git remote add`.");
+                return Ok(());
+            }
+            1 => {
+                println!(
+                    "Branch '{}' has no upstream, setting it to {}/{}.",
+                    branch, remotes[0], branch
+                );
+                remotes[0].clone()
+            }
+            _ => crate::prompt::upstream::prompt(&branch, remotes)?,
+        };
+
+        return crate::git::push::push_upstream(&remote, &branch);
+    }
+
+    // Fetching moves the remote-tracking branch to the remote's tip, so the lease has to be
+    // taken first, or a force push would overwrite commits that were never seen locally
+    let expected = crate::git::branch::upstream_hash()?;
 
+    let status = crate::git::status::status()?;
+
+    if status.commits_ahead > 0 && status.commits_behind > 0 {
+        if !crate::prompt::force_push::prompt(&status)? {
+            println!("Push skipped.");
+            return Ok(());
+        }
+
+        let (Some(remote), Some(branch)) = (
+            crate::git::branch::upstream_remote()?,
+            crate::git::branch::upstream_branch()?,
+        ) else {
+            return Err(anyhow::anyhow!(
+                "Cannot force push without a remote branch to compare against"
+            ));
+        };
+
+        return crate::git::push::force_push(&remote, &branch, &expected);
+    }
+
+    crate::git::push::push()
+}
+
+/// Brings the branch up to date with its remote before committing.
+/// Returns whether the commit flow should continue.
+fn sync(status: &Status) -> anyhow::Result<bool> {
+    if status.commits_behind == 0 {
+        return Ok(true);
+    }
+
+    let strategy = if status.commits_ahead == 0 {
+        if !crate::prompt::fast_forward::prompt(status)? {
+            return Ok(true);
+        }
+        PullStrategy::FastForward
+    } else {
+        match crate::prompt::diverged::prompt(status)? {
+            Some(strategy) => strategy,
+            None => return Ok(true),
+        }
+    };
+
+    let stashed = crate::git::stash::push()?;
+
+    if let Err(e) = crate::git::pull::pull(strategy) {
+        let conflicts = crate::git::status::conflicts()?;
+
+        if conflicts.is_empty() {
+            if stashed {
+                crate::git::stash::pop()?;
+            }
+            return Err(e);
+        }
+
+        println!("The {} resulted in conflicts:", strategy);
+        for path in conflicts {
+            println!("  {}", path);
+        }
+        println!(
+            "Resolve them and finish the {}, then run committer again.",
+            strategy
+        );
+        if stashed {
+            println!("Your local changes are kept in the stash. Restore them with `git stash pop --index`.");
+        }
+        return Ok(false);
+    }
+
+    if stashed && crate::git::stash::pop().is_err() {
+        println!(
+            "Pulled with {}, but restoring your local changes resulted in conflicts:",
+            strategy
+        );
+        for path in crate::git::status::conflicts()? {
+            println!("  {}", path);
+        }
+        println!("Your local changes are kept in the stash. Resolve the conflicts, then run committer again.");
+        return Ok(false);
+    }
+
+    println!("Pulled with {}.", strategy);
+
+    Ok(true)
+}
+
+async fn generate() -> anyhow::Result<()> {
     let staged_diff = crate::git::diff::diff(true)?;
     if staged_diff.is_empty() {
         eprintln!("No changes added to commit. Stage changes first.");
         std::process::exit(1);
     }
 
+    let message = generate_message().await.unwrap_or_else(|e| {
+        eprintln!("Failed to generate message: {}", e);
+        std::process::exit(1);
+    });
+
+    println!("{}", message);
+
+    Ok(())
+}
+
+/// Generates a full commit message for the staged changes, without any prompts.
+async fn generate_message() -> anyhow::Result<String> {
+    let emojis = Emoji::all();
+
     let diff = crate::git::diff::diff_raw()?;
 
     let scope_idx = suggest_scope(diff.clone(), &emojis).await.unwrap_or(0);
     let intention = &emojis[scope_idx];
 
-    let subject = suggest_message(diff).await.unwrap_or_else(|e| {
-        eprintln!("Failed to generate message: {}", e);
+    let subject = suggest_message(diff).await?;
+
+    let semver = intention.semver_trailer();
+
+    Ok(format!("{} {}\n\n{}", intention.emoji, subject, semver))
+}
+
+fn lockfile(check: bool) -> anyhow::Result<()> {
+    let stale = crate::updater::cargo::sync_lockfile(std::path::Path::new("."), check)?;
+
+    if stale.is_empty() {
+        println!("Cargo.lock is in sync with the workspace.");
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        if check {
+            "Cargo.lock is out of sync with the workspace:"
+        } else {
+            "Updated Cargo.lock:"
+        }
+    );
+    for entry in &stale {
+        println!("  {}", entry);
+    }
+
+    if check {
         std::process::exit(1);
-    });
+    }
+
+    Ok(())
+}
+
+fn lint(range: Option<String>, format: OutputFormat, config: &Config) -> anyhow::Result<()> {
+    let emojis = Emoji::all();
 
-    let semver = match intention.semver {
-        Some(SemVer::Major) => "semver: major",
-        Some(SemVer::Minor) => "semver: minor",
-        Some(SemVer::Patch) => "semver: patch",
-        None => "semver: chore",
+    let commits = match range {
+        Some(range) => crate::git::log::range(&range)?,
+        None => match crate::git::branch::upstream()? {
+            Some(upstream) => crate::git::log::range(&format!("{}..HEAD", upstream))?,
+            None => crate::git::log::log(1)?,
+        },
     };
 
-    println!("{} {}\n\n{}", intention.emoji, subject, semver);
+    let results = commits
+        .iter()
+        .map(|commit| (commit, crate::lint::check(commit, &emojis, &config.lint)))
+        .collect::<Vec<_>>();
+
+    let failed = results
+        .iter()
+        .filter(|(_, violations)| !violations.is_empty())
+        .count();
+
+    match format {
+        OutputFormat::Human => {
+            for (commit, violations) in results.iter().filter(|(_, v)| !v.is_empty()) {
+                println!("{} {}", commit.short_hash(), commit.subject);
+                for violation in violations {
+                    println!("  {} ({})", violation.message, violation.rule);
+                }
+            }
+
+            println!(
+                "{} {} checked, {} with violations.",
+                commits.len(),
+                if commits.len() == 1 {
+                    "commit"
+                } else {
+                    "commits"
+                },
+                failed
+            );
+        }
+        OutputFormat::Json => {
+            let report = results
+                .iter()
+                .map(|(commit, violations)| {
+                    serde_json::json!({
+                        "hash": commit.hash,
+                        "subject": commit.subject,
+                        "violations": violations,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+async fn hook(name: &str, args: &[String], config: &Config) -> anyhow::Result<()> {
+    match name {
+        "prepare-commit-msg" => {
+            let file = args
+                .first()
+                .ok_or(anyhow::anyhow!("Missing commit message file"))?;
+            crate::hooks::prepare_commit_msg(file, args.get(1).map(|s| s.as_str())).await?;
+        }
+        "commit-msg" => {
+            let file = args
+                .first()
+                .ok_or(anyhow::anyhow!("Missing commit message file"))?;
+            if !crate::hooks::commit_msg(file, config)? {
+                std::process::exit(1);
+            }
+        }
+        _ => return Err(anyhow::anyhow!("Unknown hook: {}", name)),
+    }
 
     Ok(())
 }
@@ -0,0 +1,34 @@
+use anyhow::anyhow;
+use anyhow::Result;
+
+use crate::git::{pull::PullStrategy, status::Status};
+
+/// Asks how to reconcile a branch that is both ahead of and behind its remote.
+/// Returns `None` when the user wants to skip syncing.
+pub fn prompt(status: &Status) -> Result<Option<PullStrategy>> {
+    let options = vec!["Rebase", "Merge", "Skip"];
+
+    let choice = inquire::Select::new(
+        format!(
+            "You are ahead by {} and behind by {} {}. How do you want to sync with the remote?",
+            status.commits_ahead,
+            status.commits_behind,
+            if status.commits_behind == 1 {
+                "commit"
+            } else {
+                "commits"
+            }
+        )
+        .as_str(),
+        options,
+    )
+    .with_help_message("Local changes are stashed and restored afterwards")
+    .prompt()
+    .map_err(|e| anyhow!(e))?;
+
+    Ok(match choice {
+        "Rebase" => Some(PullStrategy::Rebase),
+        "Merge" => Some(PullStrategy::Merge),
+        _ => None,
+    })
+}
@@ -0,0 +1,14 @@
+use anyhow::anyhow;
+use anyhow::Result;
+
+use crate::git::commit::FixupKind;
+
+pub fn prompt() -> Result<FixupKind> {
+    inquire::Select::new(
+        "Kind:",
+        vec![FixupKind::Fixup, FixupKind::Squash, FixupKind::Amend],
+    )
+    .with_help_message("How should the changes be folded into the target?")
+    .prompt()
+    .map_err(|e| anyhow!(e))
+}
@@ -0,0 +1,30 @@
+use anyhow::anyhow;
+use anyhow::Result;
+use std::fmt::{Display, Formatter};
+
+use crate::git::log::Commit;
+
+pub struct Target<'a> {
+    pub commit: &'a Commit,
+    pub shared_files: usize,
+}
+
+pub fn prompt<'a>(targets: Vec<Target<'a>>) -> Result<&'a Commit> {
+    inquire::Select::new("Target:", targets)
+        .with_help_message("Which commit do you want to fix up? Likely targets are listed first")
+        .prompt()
+        .map(|target| target.commit)
+        .map_err(|e| anyhow!(e))
+}
+
+impl Display for Target<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.commit.short_hash(), self.commit.subject)?;
+
+        match self.shared_files {
+            0 => Ok(()),
+            1 => write!(f, " (1 shared file)"),
+            n => write!(f, " ({} shared files)", n),
+        }
+    }
+}
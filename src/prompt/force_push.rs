@@ -0,0 +1,18 @@
+use anyhow::anyhow;
+use anyhow::Result;
+
+use crate::git::status::Status;
+
+pub fn prompt(status: &Status) -> Result<bool> {
+    inquire::Confirm::new(
+        format!(
+            "Your branch has diverged from the remote ({} ahead, {} behind). Force push with lease?",
+            status.commits_ahead, status.commits_behind
+        )
+        .as_str(),
+    )
+    .with_help_message("This overwrites the remote branch, unless someone else pushed to it since your last fetch")
+    .with_default(false)
+    .prompt()
+    .map_err(|e| anyhow!(e))
+}
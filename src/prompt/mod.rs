@@ -1,3 +1,8 @@
+pub mod diverged;
 pub mod fast_forward;
+pub mod fixup_kind;
+pub mod fixup_target;
+pub mod force_push;
 pub mod push;
 pub mod subject;
+pub mod upstream;
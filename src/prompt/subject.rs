@@ -6,6 +6,7 @@ pub fn prompt(
     intention: &Emoji,
     previous_subjects: Vec<String>,
     default: Option<String>,
+    initial: Option<String>,
 ) -> anyhow::Result<String> {
     let description = match intention.semver {
         Some(SemVer::Major) => "Describe the breaking change",
@@ -28,6 +29,12 @@ pub fn prompt(
         text
     };
 
+    let text = if let Some(ref initial_value) = initial {
+        text.with_initial_value(initial_value)
+    } else {
+        text
+    };
+
     let result = text.prompt();
 
     result.map_err(anyhow::Error::new)
@@ -0,0 +1,18 @@
+use anyhow::anyhow;
+use anyhow::Result;
+
+pub fn prompt(branch: &str, remotes: Vec<String>) -> Result<String> {
+    let starting_cursor = remotes.iter().position(|r| r == "origin").unwrap_or(0);
+
+    inquire::Select::new(
+        format!(
+            "Branch '{}' has no upstream. Which remote do you want to push to?",
+            branch
+        )
+        .as_str(),
+        remotes,
+    )
+    .with_starting_cursor(starting_cursor)
+    .prompt()
+    .map_err(|e| anyhow!(e))
+}
@@ -0,0 +1,323 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, helper::ReleaseInfo, updater::VersionUpdater};
+use anyhow::{Context as _, Result};
+
+/// Bumps the version files, commits them as a release commit and tags that commit.
+/// When a remote is given, the branch and tag are pushed as well.
+/// If any step fails, the commit, the tag, the index and the manifest changes are rolled back.
+pub fn release(info: &ReleaseInfo, config: &Config, remote: Option<&str>) -> Result<()> {
+    check(info, config)?;
+
+    let head = crate::git::log::head_hash()?;
+    let updaters = crate::updater::configured(config, &info.dir)?;
+    let mut files = files(&updaters)?;
+
+    let index = crate::git::index::write_tree()?;
+
+    let changelog = crate::updater::join(&info.dir, &config.changelog.file);
+    if changelog.exists() {
+        files.push(changelog.clone());
+    }
+
+    let originals = files
+        .iter()
+        .map(|file| {
+            let content =
+                fs::read(file).with_context(|| format!("Failed to read {}", file.display()))?;
+            Ok((file.clone(), content))
+        })
+        .collect::<Result<Vec<(PathBuf, Vec<u8>)>>>()?;
+
+    let mut progress = Progress::default();
+
+    let result = run(info, config, remote, &updaters, &changelog, &mut progress);
+
+    if let Err(e) = result {
+        println!("Release failed, rolling back.");
+        rollback(info, &head, &index, &originals, &progress)?;
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Fails if the release can't go ahead, before anything is changed.
+pub fn check(info: &ReleaseInfo, config: &Config) -> Result<()> {
+    if crate::git::tag::exists(&info.tag)? {
+        return Err(anyhow::anyhow!("Tag {} already exists", info.tag));
+    }
+
+    // The release commit takes these files as they are, so unrelated edits would end up in it
+    let updaters = crate::updater::configured(config, &info.dir)?;
+    let paths = files(&updaters)?
+        .iter()
+        .map(|file| file.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    let dirty = crate::git::status::dirty(&paths.iter().map(|p| p.as_str()).collect::<Vec<_>>())?;
+    if !dirty.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Refusing to release with uncommitted changes in {}, commit or stash them first",
+            dirty.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Progress {
+    staged: bool,
+    committed: bool,
+    tagged: bool,
+}
+
+fn run(
+    info: &ReleaseInfo,
+    config: &Config,
+    remote: Option<&str>,
+    updaters: &[Box<dyn VersionUpdater>],
+    changelog: &Path,
+    progress: &mut Progress,
+) -> Result<()> {
+    let mut files = Vec::new();
+
+    for updater in updaters {
+        for file in updater.set_version(&info.version)? {
+            println!("Updated {} ({}).", file.display(), updater.name());
+            files.push(file);
+        }
+    }
+
+    if files.is_empty() {
+        println!("No version files to update.");
+    }
+
+    let base = info.base.as_ref().map(|base| base.name.as_str());
+    if crate::changelog::release(
+        changelog,
+        &info.version,
+        &info.changelog,
+        &info.tag,
+        base,
+        config,
+    )? {
+        println!("Updated {}.", changelog.display());
+        files.push(changelog.to_path_buf());
+    }
+
+    if config.tag.release_commit {
+        let changed = files
+            .iter()
+            .map(|file| file.strip_prefix(".").unwrap_or(file))
+            .map(|file| file.to_string_lossy().to_string())
+            .filter(|file| !crate::git::status::is_ignored(file))
+            .collect::<Vec<String>>();
+
+        if !changed.is_empty() {
+            let paths = changed.iter().map(|f| f.as_str()).collect::<Vec<&str>>();
+            crate::git::add::add(&paths)?;
+            progress.staged = true;
+
+            // Adding leaves no unstaged changes in these paths, so any left are staged
+            if !crate::git::status::dirty(&paths)?.is_empty() {
+                let release = match &info.package {
+                    Some(package) => format!("{} {}", package, info.version),
+                    None => info.version.to_string(),
+                };
+                let message = format!("🔖 release {}\n\nsemver: chore", release);
+                crate::git::commit::commit_paths(message, &paths, &config.signing.commits())?;
+                progress.committed = true;
+            }
+        }
+    }
+
+    crate::git::tag::tag(info.tag.clone(), &info.changelog, &config.signing.tags())?;
+    progress.tagged = true;
+
+    if let Some(remote) = remote {
+        if progress.committed {
+            let branch = crate::git::branch::current()?;
+            crate::git::push::push_release(remote, &branch, &info.tag)?;
+        } else {
+            crate::git::push::push_tag(remote, &info.tag)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn rollback(
+    info: &ReleaseInfo,
+    head: &str,
+    index: &str,
+    originals: &[(PathBuf, Vec<u8>)],
+    progress: &Progress,
+) -> Result<()> {
+    if progress.tagged {
+        crate::git::tag::delete(&info.tag)?;
+    }
+
+    if progress.committed {
+        crate::git::reset::soft(head)?;
+    }
+
+    if progress.staged {
+        crate::git::index::read_tree(index)?;
+    }
+
+    for (file, content) in originals {
+        fs::write(file, content)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the files the updaters may modify.
+pub fn files(updaters: &[Box<dyn VersionUpdater>]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for updater in updaters {
+        for file in updater.files()? {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+    use crate::config::{UpdaterConfig, UpdaterKind};
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Runs `test` in a new repository with a committed `VERSION` and `notes.txt`.
+    fn in_repository(name: &str, test: impl FnOnce(&Path)) {
+        let dir = std::env::temp_dir().join(format!("committer-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        git(&dir, &["init", "--quiet"]);
+        git(&dir, &["config", "user.name", "Test"]);
+        git(&dir, &["config", "user.email", "test@example.com"]);
+        git(&dir, &["config", "commit.gpgsign", "false"]);
+        git(&dir, &["config", "tag.gpgsign", "false"]);
+        fs::write(dir.join("VERSION"), "0.1.0\n").unwrap();
+        fs::write(dir.join("notes.txt"), "one\n").unwrap();
+        git(&dir, &["add", "."]);
+        git(&dir, &["commit", "--quiet", "-m", "initial"]);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            crate::cmd::in_directory(&dir, || test(&dir))
+        }));
+
+        let _ = fs::remove_dir_all(&dir);
+
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            updaters: vec![UpdaterConfig {
+                kind: UpdaterKind::Plain,
+                path: None,
+                pattern: None,
+            }],
+            ..Config::default()
+        }
+    }
+
+    fn info(dir: &Path) -> ReleaseInfo {
+        ReleaseInfo {
+            tag: "v0.2.0".to_string(),
+            base: None,
+            version: semver::Version::new(0, 2, 0),
+            changelog: "### Fixes\n- 🐛 fix".to_string(),
+            package: None,
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Everything a rollback has to restore: HEAD, the index, the working tree and the tags.
+    fn snapshot(dir: &Path) -> Vec<String> {
+        vec![
+            git(dir, &["rev-parse", "HEAD"]),
+            git(dir, &["write-tree"]),
+            git(dir, &["status", "--porcelain"]),
+            fs::read_to_string(dir.join("VERSION")).unwrap(),
+            fs::read_to_string(dir.join("notes.txt")).unwrap(),
+            git(dir, &["tag", "--list", "--format=%(refname) %(objectname)"]),
+        ]
+    }
+
+    #[test]
+    fn releases_with_a_commit_and_a_tag() {
+        in_repository("release", |dir| {
+            release(&info(dir), &config(), None).unwrap();
+
+            assert_eq!(fs::read_to_string(dir.join("VERSION")).unwrap(), "0.2.0\n");
+            assert_eq!(
+                git(dir, &["log", "-1", "--format=%s"]),
+                "🔖 release 0.2.0\n"
+            );
+            assert_eq!(
+                git(dir, &["rev-list", "-1", "v0.2.0"]),
+                git(dir, &["rev-parse", "HEAD"])
+            );
+        });
+    }
+
+    #[test]
+    fn rollback_restores_the_repository_exactly() {
+        in_repository("rollback", |dir| {
+            // Unrelated work in progress, partly staged
+            fs::write(dir.join("notes.txt"), "two\n").unwrap();
+            git(dir, &["add", "notes.txt"]);
+            fs::write(dir.join("notes.txt"), "three\n").unwrap();
+
+            let before = snapshot(dir);
+
+            // Pushing fails after the release commit and the tag are made
+            let error = release(&info(dir), &config(), Some("nonexistent")).unwrap_err();
+            assert!(format!("{:#}", error).contains("nonexistent"));
+
+            assert_eq!(snapshot(dir), before);
+            assert!(git(dir, &["tag", "--list"]).is_empty());
+            assert_eq!(git(dir, &["log", "-1", "--format=%s"]), "initial\n");
+        });
+    }
+
+    #[test]
+    fn refuses_to_release_uncommitted_version_files() {
+        in_repository("dirty", |dir| {
+            fs::write(dir.join("VERSION"), "0.1.0-dev\n").unwrap();
+
+            let before = snapshot(dir);
+            let error = release(&info(dir), &config(), None).unwrap_err();
+
+            assert!(error.to_string().contains("VERSION"));
+            assert_eq!(snapshot(dir), before);
+        });
+    }
+}
@@ -1,25 +1,656 @@
-use std::fs;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
 
-pub fn set_version(version: &semver::Version) -> anyhow::Result<()> {
-    // TODO: Only update Cargo.toml files that are not git ignored
+use toml_edit::{DocumentMut, Item, Value};
 
-    // Find all Cargo.toml files in the repository
-    let cargo_files = fs::read_dir(".")?
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.file_name().to_string_lossy().contains("Cargo.toml"))
-        .map(|entry| entry.path())
-        .collect::<Vec<_>>();
+use super::VersionUpdater;
+
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Sets `[package].version` and `[workspace.package].version` in the manifest in `dir` and its
+/// workspace members, along with the versions of path dependencies on them anywhere in the
+/// enclosing workspace. Members that inherit their version with `version.workspace = true`
+/// are left alone. Returns the manifests that changed.
+pub fn set_version(dir: &Path, version: &semver::Version) -> anyhow::Result<Vec<PathBuf>> {
+    let own = manifests(dir)?;
+    let cargo_files = with_workspace(dir, own.clone())?;
+
+    let mut documents = cargo_files
+        .iter()
+        .map(|file| Ok((file, fs::read_to_string(file)?.parse::<DocumentMut>()?)))
+        .collect::<anyhow::Result<Vec<(&PathBuf, DocumentMut)>>>()?;
+
+    let members = documents
+        .iter()
+        .filter(|(file, _)| own.contains(file))
+        .filter_map(|(_, document)| document.get("package")?.get("name")?.as_str())
+        .map(|name| name.to_string())
+        .collect::<HashSet<String>>();
+
+    let mut updated = Vec::new();
 
     // Update the version in all Cargo.toml files
-    for file in cargo_files {
-        let content = fs::read_to_string(&file)?;
-        let regex = regex::Regex::new(r#"version\s*=\s*".+""#)?;
-        let updated_content = regex
-            .replace(&content, format!("version = \"{}\"", version))
+    for (file, document) in documents.iter_mut() {
+        let mut changed = false;
+
+        // The rest of the workspace only gets its dependency versions updated
+        if own.contains(file) {
+            changed |= set_package_version(document, version);
+        }
+
+        for table in dependency_tables(document) {
+            changed |= set_dependency_versions(table, &members, version);
+        }
+
+        if changed {
+            fs::write(&file, document.to_string())?;
+            updated.push(file.to_path_buf());
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Updates the Cargo package or workspace in a directory, and the `Cargo.lock` of its workspace.
+pub struct Cargo {
+    pub dir: PathBuf,
+}
+
+impl VersionUpdater for Cargo {
+    fn name(&self) -> String {
+        "cargo".to_string()
+    }
+
+    fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = with_workspace(&self.dir, manifests(&self.dir)?)?;
+
+        let lockfile = super::join(&workspace_root(&self.dir), "Cargo.lock");
+        if lockfile.exists() {
+            files.push(lockfile);
+        }
+
+        Ok(files)
+    }
+
+    fn set_version(&self, version: &semver::Version) -> anyhow::Result<Vec<PathBuf>> {
+        let mut changed = set_version(&self.dir, version)?;
+
+        let root = workspace_root(&self.dir);
+        if !sync_lockfile(&root, false)?.is_empty() {
+            changed.push(super::join(&root, "Cargo.lock"));
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Updates the `Cargo.lock` entries of workspace packages to the versions in their manifests,
+/// without resolving anything over the network. In check mode, nothing is written.
+/// Returns the entries that were, or would be, updated.
+pub fn sync_lockfile(dir: &Path, check: bool) -> anyhow::Result<Vec<String>> {
+    let lockfile = super::join(dir, "Cargo.lock");
+
+    if !lockfile.exists() {
+        return Ok(vec![]);
+    }
+
+    let versions = workspace_versions(dir)?;
+    let mut document = fs::read_to_string(&lockfile)?.parse::<DocumentMut>()?;
+
+    let Some(packages) = document
+        .get_mut("package")
+        .and_then(|p| p.as_array_of_tables_mut())
+    else {
+        return Ok(vec![]);
+    };
+
+    let mut stale = Vec::new();
+    // Dependencies are listed as `name version` when a name appears with several versions
+    let mut renames = HashMap::new();
+
+    for package in packages.iter_mut() {
+        // Workspace packages are the only ones without a registry or git source
+        if package.contains_key("source") {
+            continue;
+        }
+
+        let Some(name) = package.get("name").and_then(|n| n.as_str()) else {
+            continue;
+        };
+        let Some(expected) = versions.get(name) else {
+            continue;
+        };
+        let name = name.to_string();
+
+        if let Some(item) = package.get_mut("version") {
+            let current = item.as_str().unwrap_or_default().to_string();
+
+            if &current != expected {
+                stale.push(format!("{} {} -> {}", name, current, expected));
+                renames.insert(
+                    format!("{} {}", name, current),
+                    format!("{} {}", name, expected),
+                );
+
+                if !check {
+                    set_string(item, expected);
+                }
+            }
+        }
+    }
+
+    if check || stale.is_empty() {
+        return Ok(stale);
+    }
+
+    for package in packages.iter_mut() {
+        let Some(dependencies) = package
+            .get_mut("dependencies")
+            .and_then(|d| d.as_array_mut())
+        else {
+            continue;
+        };
+
+        for dependency in dependencies.iter_mut() {
+            if let Some(renamed) = dependency.as_str().and_then(|d| renames.get(d)) {
+                set_value(dependency, renamed);
+            }
+        }
+    }
+
+    fs::write(&lockfile, document.to_string())?;
+
+    Ok(stale)
+}
+
+/// Returns the root Cargo.toml and those of its workspace members, skipping git-ignored files.
+pub fn manifests(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let root = super::join(dir, "Cargo.toml");
+
+    if !root.exists() {
+        return Ok(vec![]);
+    }
+
+    let document = fs::read_to_string(&root)?.parse::<DocumentMut>()?;
+
+    let mut cargo_files = vec![root];
+    cargo_files.extend(members(dir, &document)?);
+
+    let cargo_files = cargo_files
+        .into_iter()
+        .filter(|file| !crate::git::status::is_ignored(&file.to_string_lossy()))
+        .collect::<Vec<_>>();
+
+    Ok(cargo_files)
+}
+
+/// Returns `[package].name` from the `Cargo.toml` in the current directory, if any.
+pub fn package_name() -> anyhow::Result<Option<String>> {
+    let content = match fs::read_to_string("Cargo.toml") {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+
+    let manifest: toml::Table = toml::from_str(&content)?;
+
+    Ok(manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .map(|n| n.to_string()))
+}
+
+/// Returns the version of every package in the workspace, resolving `version.workspace = true`.
+fn workspace_versions(dir: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let documents = manifests(dir)?
+        .iter()
+        .map(|file| Ok(fs::read_to_string(file)?.parse::<DocumentMut>()?))
+        .collect::<anyhow::Result<Vec<DocumentMut>>>()?;
+
+    let workspace_version = documents.iter().find_map(|document| {
+        document
+            .get("workspace")?
+            .get("package")?
+            .get("version")?
+            .as_str()
+    });
+
+    let versions = documents
+        .iter()
+        .filter_map(|document| {
+            let package = document.get("package")?;
+            let name = package.get("name")?.as_str()?;
+            let version = match package.get("version")?.as_str() {
+                Some(version) => version,
+                None => workspace_version?,
+            };
+
+            Some((name.to_string(), version.to_string()))
+        })
+        .collect();
+
+    Ok(versions)
+}
+
+/// Expands `[workspace].members`, minus `[workspace].exclude`, into member manifests.
+fn members(dir: &Path, document: &DocumentMut) -> anyhow::Result<Vec<PathBuf>> {
+    let Some(workspace) = document.get("workspace") else {
+        return Ok(vec![]);
+    };
+
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|item| item.as_array())
+            .map(|array| {
+                array
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|v| v.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let excluded = patterns("exclude")
+        .iter()
+        .map(|path| super::join(dir, path))
+        .collect::<Vec<_>>();
+
+    let mut manifests = Vec::new();
+
+    for pattern in patterns("members") {
+        let pattern = super::join(dir, &pattern);
+
+        for member in glob::glob(&pattern.to_string_lossy())?.filter_map(|entry| entry.ok()) {
+            let manifest = member.join("Cargo.toml");
+
+            if member.is_dir()
+                && manifest.exists()
+                && !excluded.iter().any(|e| member.starts_with(e))
+                && !manifests.contains(&manifest)
+            {
+                manifests.push(manifest);
+            }
+        }
+    }
+
+    Ok(manifests)
+}
+
+/// Sets `[package].version` and `[workspace.package].version`. Returns whether either changed.
+fn set_package_version(document: &mut DocumentMut, version: &semver::Version) -> bool {
+    let mut changed = false;
+
+    if let Some(package) = document.get_mut("package") {
+        if let Some(item) = package.get_mut("version") {
+            changed |= set_string(item, &version.to_string());
+        }
+    }
+
+    if let Some(package) = document
+        .get_mut("workspace")
+        .and_then(|workspace| workspace.get_mut("package"))
+    {
+        if let Some(item) = package.get_mut("version") {
+            changed |= set_string(item, &version.to_string());
+        }
+    }
+
+    changed
+}
+
+/// Adds the manifests of the workspace enclosing `dir`, if `dir` is inside one.
+fn with_workspace(dir: &Path, mut manifests: Vec<PathBuf>) -> anyhow::Result<Vec<PathBuf>> {
+    for manifest in self::manifests(&workspace_root(dir))? {
+        if !manifests.contains(&manifest) {
+            manifests.push(manifest);
+        }
+    }
+
+    Ok(manifests)
+}
+
+/// Returns the nearest directory from `dir` upwards whose manifest has a `[workspace]` table,
+/// or `dir` itself when it isn't part of a workspace.
+fn workspace_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|ancestor| {
+            fs::read_to_string(super::join(ancestor, "Cargo.toml"))
+                .ok()
+                .and_then(|content| content.parse::<DocumentMut>().ok())
+                .is_some_and(|document| document.contains_key("workspace"))
+        })
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// Returns every dependency table in the manifest, including target-specific
+/// ones and `[workspace.dependencies]`.
+fn dependency_tables(document: &mut DocumentMut) -> Vec<&mut Item> {
+    let mut tables = Vec::new();
+
+    for (key, item) in document.iter_mut() {
+        match key.get() {
+            "target" => {
+                if let Some(targets) = item.as_table_like_mut() {
+                    for (_, target) in targets.iter_mut() {
+                        if let Some(target) = target.as_table_like_mut() {
+                            for (key, table) in target.iter_mut() {
+                                if DEPENDENCY_TABLES.contains(&key.get()) {
+                                    tables.push(table);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            "workspace" => {
+                if let Some(dependencies) = item.get_mut("dependencies") {
+                    tables.push(dependencies);
+                }
+            }
+            key if DEPENDENCY_TABLES.contains(&key) => tables.push(item),
+            _ => {}
+        }
+    }
+
+    tables
+}
+
+/// Updates the version requirement of path dependencies on workspace members,
+/// keeping the requirement operator, e.g. `=0.1.0` becomes `=0.2.0`.
+fn set_dependency_versions(
+    table: &mut Item,
+    members: &HashSet<String>,
+    version: &semver::Version,
+) -> bool {
+    let Some(table) = table.as_table_like_mut() else {
+        return false;
+    };
+
+    let mut changed = false;
+
+    for (name, dependency) in table.iter_mut() {
+        let Some(dependency) = dependency.as_table_like_mut() else {
+            continue;
+        };
+
+        let package = dependency
+            .get("package")
+            .and_then(|p| p.as_str())
+            .unwrap_or(name.get())
             .to_string();
 
-        fs::write(&file, updated_content)?;
+        if dependency.get("path").is_none() || !members.contains(&package) {
+            continue;
+        }
+
+        if let Some(item) = dependency.get_mut("version") {
+            let operator = item
+                .as_str()
+                .map(|current| {
+                    current
+                        .chars()
+                        .take_while(|c| "=^~<>".contains(*c))
+                        .collect::<String>()
+                })
+                .unwrap_or_default();
+
+            changed |= set_string(item, &format!("{}{}", operator, version));
+        }
+    }
+
+    changed
+}
+
+/// Replaces a string value, keeping its surrounding whitespace and comments.
+/// Returns whether the value changed.
+pub(super) fn set_string(item: &mut Item, value: &str) -> bool {
+    let Some(current) = item.as_value_mut() else {
+        return false;
+    };
+
+    // `version.workspace = true` is a table, so only plain strings are replaced
+    if current.as_str().is_none() || current.as_str() == Some(value) {
+        return false;
+    }
+
+    set_value(current, value);
+
+    true
+}
+
+fn set_value(current: &mut Value, value: &str) {
+    let decor = current.decor().clone();
+    *current = Value::from(value);
+    *current.decor_mut() = decor;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates the files in a new temporary directory.
+    fn workspace(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("committer-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        for (file, content) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        dir
+    }
+
+    fn version(version: &str) -> semver::Version {
+        semver::Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn keeps_formatting_and_comments() {
+        let mut document = r#"[package]
+name = "app" # the binary
+version   =   "0.1.0" # bumped on release
+edition = "2021"
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        assert!(set_package_version(&mut document, &version("0.2.0")));
+        assert_eq!(
+            document.to_string(),
+            r#"[package]
+name = "app" # the binary
+version   =   "0.2.0" # bumped on release
+edition = "2021"
+"#
+        );
+        assert!(!set_package_version(&mut document, &version("0.2.0")));
+    }
+
+    #[test]
+    fn leaves_inherited_versions_alone() {
+        let mut document = "[package]\nname = \"app\"\nversion.workspace = true\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(!set_package_version(&mut document, &version("0.2.0")));
+    }
+
+    #[test]
+    fn updates_path_dependencies_on_members_only() {
+        let mut document = r#"[dependencies]
+core = { path = "../core", version = "=0.1.0" }
+renamed = { path = "../core", package = "core", version = "^0.1.0" }
+serde = { version = "0.1.0" }
+other = { path = "../other", version = "0.1.0" }
+
+[target.'cfg(unix)'.dev-dependencies]
+core = { path = "../core", version = "0.1.0" }
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        let members = HashSet::from(["core".to_string()]);
+        for table in dependency_tables(&mut document) {
+            set_dependency_versions(table, &members, &version("0.2.0"));
+        }
+
+        assert_eq!(
+            document.to_string(),
+            r#"[dependencies]
+core = { path = "../core", version = "=0.2.0" }
+renamed = { path = "../core", package = "core", version = "^0.2.0" }
+serde = { version = "0.1.0" }
+other = { path = "../other", version = "0.1.0" }
+
+[target.'cfg(unix)'.dev-dependencies]
+core = { path = "../core", version = "0.2.0" }
+"#
+        );
+    }
+
+    #[test]
+    fn updates_a_member_and_its_dependents() {
+        let dir = workspace(
+            "cargo-member",
+            &[
+                (
+                    "Cargo.toml",
+                    "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.package]\nversion = \"0.1.0\"\n",
+                ),
+                (
+                    "crates/core/Cargo.toml",
+                    "[package]\nname = \"core\"\nversion = \"0.1.0\"\n",
+                ),
+                (
+                    "crates/app/Cargo.toml",
+                    "[package]\nname = \"app\"\nversion.workspace = true\n\n[dependencies]\ncore = { path = \"../core\", version = \"0.1.0\" }\n",
+                ),
+            ],
+        );
+
+        let changed = set_version(&dir.join("crates/core"), &version("0.2.0")).unwrap();
+
+        let read = |file: &str| fs::read_to_string(dir.join(file)).unwrap();
+        assert_eq!(changed.len(), 2);
+        assert!(read("crates/core/Cargo.toml").contains("version = \"0.2.0\""));
+        assert!(read("crates/app/Cargo.toml")
+            .contains("core = { path = \"../core\", version = \"0.2.0\" }"));
+        // The workspace and its other members keep their own versions
+        assert!(read("Cargo.toml").contains("version = \"0.1.0\""));
+        assert!(read("crates/app/Cargo.toml").contains("version.workspace = true"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "core",
+ "serde 1.0.0",
+]
+
+[[package]]
+name = "core"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    fn locked_workspace(name: &str) -> PathBuf {
+        workspace(
+            name,
+            &[
+                (
+                    "Cargo.toml",
+                    "[workspace]\nmembers = [\"app\", \"core\"]\n\n[workspace.package]\nversion = \"0.2.0\"\n",
+                ),
+                (
+                    "app/Cargo.toml",
+                    "[package]\nname = \"app\"\nversion.workspace = true\n",
+                ),
+                (
+                    "core/Cargo.toml",
+                    "[package]\nname = \"core\"\nversion = \"0.1.0\"\n",
+                ),
+                ("Cargo.lock", LOCKFILE),
+            ],
+        )
     }
 
-    Ok(())
+    #[test]
+    fn syncs_workspace_packages_in_the_lockfile() {
+        let dir = locked_workspace("cargo-lock");
+
+        let stale = sync_lockfile(&dir, false).unwrap();
+
+        assert_eq!(stale, ["app 0.1.0 -> 0.2.0"]);
+        // Registry packages with the same version are left alone
+        assert_eq!(
+            fs::read_to_string(dir.join("Cargo.lock")).unwrap(),
+            LOCKFILE.replacen("version = \"0.1.0\"", "version = \"0.2.0\"", 1)
+        );
+        assert!(sync_lockfile(&dir, false).unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn checks_the_lockfile_without_writing() {
+        let dir = locked_workspace("cargo-lock-check");
+
+        assert_eq!(sync_lockfile(&dir, true).unwrap(), ["app 0.1.0 -> 0.2.0"]);
+        assert_eq!(
+            fs::read_to_string(dir.join("Cargo.lock")).unwrap(),
+            LOCKFILE
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn renames_versioned_dependencies() {
+        let dir = workspace(
+            "cargo-lock-renames",
+            &[
+                (
+                    "Cargo.toml",
+                    "[workspace]\nmembers = [\"core\"]\n",
+                ),
+                (
+                    "core/Cargo.toml",
+                    "[package]\nname = \"core\"\nversion = \"0.2.0\"\n",
+                ),
+                (
+                    "Cargo.lock",
+                    "[[package]]\nname = \"app\"\nversion = \"1.0.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\ndependencies = [\"core 0.1.0\"]\n\n[[package]]\nname = \"core\"\nversion = \"0.1.0\"\n",
+                ),
+            ],
+        );
+
+        sync_lockfile(&dir, false).unwrap();
+
+        let lockfile = fs::read_to_string(dir.join("Cargo.lock")).unwrap();
+        assert!(lockfile.contains("dependencies = [\"core 0.2.0\"]"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1 +1,82 @@
 pub mod cargo;
+pub mod npm;
+pub mod pattern;
+pub mod plain;
+pub mod python;
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, UpdaterConfig, UpdaterKind};
+
+/// Writes a new version into the files of one ecosystem.
+pub trait VersionUpdater {
+    /// A short name for reports, e.g. `cargo` or `package.json`.
+    fn name(&self) -> String;
+
+    /// The files the updater may modify, so they can be restored if the release fails.
+    fn files(&self) -> anyhow::Result<Vec<PathBuf>>;
+
+    /// Writes the version and returns the files that changed.
+    fn set_version(&self, version: &semver::Version) -> anyhow::Result<Vec<PathBuf>>;
+}
+
+/// Returns the updaters configured in `committer.toml`, or the Cargo workspace updater if none are.
+/// Their paths are relative to `dir`, the directory of the package being released.
+pub fn configured(config: &Config, dir: &Path) -> anyhow::Result<Vec<Box<dyn VersionUpdater>>> {
+    if config.updaters.is_empty() {
+        return Ok(vec![Box::new(cargo::Cargo {
+            dir: dir.to_path_buf(),
+        })]);
+    }
+
+    config
+        .updaters
+        .iter()
+        .map(|updater| self::updater(updater, dir))
+        .collect()
+}
+
+fn updater(config: &UpdaterConfig, dir: &Path) -> anyhow::Result<Box<dyn VersionUpdater>> {
+    let path = |default: &str| join(dir, config.path.as_deref().unwrap_or(Path::new(default)));
+
+    let updater: Box<dyn VersionUpdater> = match config.kind {
+        UpdaterKind::Cargo => Box::new(cargo::Cargo { dir: path(".") }),
+        UpdaterKind::PackageJson => Box::new(npm::PackageJson {
+            path: path("package.json"),
+        }),
+        UpdaterKind::Pyproject => Box::new(python::Pyproject {
+            path: path("pyproject.toml"),
+        }),
+        UpdaterKind::HelmChart => Box::new(pattern::Pattern::helm_chart(path("Chart.yaml"))),
+        UpdaterKind::Nix => Box::new(pattern::Pattern::nix(path("flake.nix"))),
+        UpdaterKind::Plain => Box::new(plain::Plain {
+            path: path("VERSION"),
+        }),
+        UpdaterKind::Regex => {
+            let Some(path) = config.path.as_ref().map(|path| join(dir, path)) else {
+                return Err(anyhow::anyhow!("The regex updater needs a path"));
+            };
+            let Some(regex) = &config.pattern else {
+                return Err(anyhow::anyhow!(
+                    "The regex updater for {} needs a pattern",
+                    path.display()
+                ));
+            };
+
+            Box::new(pattern::Pattern::new(path, regex)?)
+        }
+    };
+
+    Ok(updater)
+}
+
+/// Joins a file to a directory, leaving out a leading `./` so paths match git's output.
+pub(crate) fn join(dir: &Path, file: impl AsRef<Path>) -> PathBuf {
+    if dir == Path::new(".") || dir.as_os_str().is_empty() {
+        file.as_ref().to_path_buf()
+    } else if file.as_ref() == Path::new(".") {
+        dir.to_path_buf()
+    } else {
+        dir.join(file)
+    }
+}
@@ -0,0 +1,162 @@
+use std::{fs, ops::Range, path::PathBuf};
+
+use anyhow::Context as _;
+
+use super::VersionUpdater;
+
+/// Updates the top-level `version` in a `package.json`, leaving the rest of the file untouched.
+pub struct PackageJson {
+    pub path: PathBuf,
+}
+
+impl VersionUpdater for PackageJson {
+    fn name(&self) -> String {
+        "package.json".to_string()
+    }
+
+    fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(vec![self.path.clone()])
+    }
+
+    fn set_version(&self, version: &semver::Version) -> anyhow::Result<Vec<PathBuf>> {
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        let package: serde_json::Value = serde_json::from_str(&content)?;
+
+        let Some(current) = package.get("version").and_then(|v| v.as_str()) else {
+            return Err(anyhow::anyhow!(
+                "{} has no top-level version",
+                self.path.display()
+            ));
+        };
+
+        if current == version.to_string() {
+            return Ok(vec![]);
+        }
+
+        // Replace the value in place, as serde_json would reorder the keys
+        let Some(span) = top_level_value(&content, "version") else {
+            return Err(anyhow::anyhow!(
+                "Failed to locate the version in {}",
+                self.path.display()
+            ));
+        };
+        let updated = format!(
+            "{}{}{}",
+            &content[..span.start],
+            version,
+            &content[span.end..]
+        );
+
+        fs::write(&self.path, updated.as_bytes())?;
+
+        Ok(vec![self.path.clone()])
+    }
+}
+
+/// Returns the span of a string value of the top-level object, without its quotes.
+/// Keys of nested objects, like a dependency named `version`, are skipped.
+fn top_level_value(json: &str, key: &str) -> Option<Range<usize>> {
+    let bytes = json.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth -= 1,
+            b'"' => {
+                let string = string_at(bytes, i)?;
+                i = string.end + 1;
+
+                let rest = json[i..].trim_start();
+                if depth == 1 && rest.starts_with(':') && &json[string.clone()] == key {
+                    let value = rest[1..].trim_start();
+                    let start = json.len() - value.len();
+
+                    return match value.starts_with('"') {
+                        true => string_at(bytes, start),
+                        false => None,
+                    };
+                }
+
+                continue;
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+/// Returns the span of the contents of the string whose opening quote is at `start`.
+fn string_at(bytes: &[u8], start: usize) -> Option<Range<usize>> {
+    let mut i = start + 1;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(start + 1..i),
+            _ => i += 1,
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_version(content: &str, version: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "committer-package-{}-{}.json",
+            std::process::id(),
+            content.len()
+        ));
+        fs::write(&path, content).unwrap();
+
+        let updater = PackageJson { path: path.clone() };
+        updater
+            .set_version(&semver::Version::parse(version).unwrap())
+            .unwrap();
+
+        let updated = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        updated
+    }
+
+    #[test]
+    fn skips_nested_versions() {
+        let content = r#"{
+  "name": "app",
+  "engines": { "version": "1.0.0" },
+  "dependencies": { "version": "1.0.0" },
+  "description": "a \"version\": \"1.0.0\" in a string",
+  "version": "1.0.0"
+}
+"#;
+
+        assert_eq!(
+            set_version(content, "1.1.0"),
+            content.replace(
+                r#""version": "1.0.0"
+}"#,
+                r#""version": "1.1.0"
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_the_formatting() {
+        let content = "{\"version\" :\t\"0.1.0\",\"name\":\"app\"}";
+
+        assert_eq!(
+            set_version(content, "0.2.0"),
+            "{\"version\" :\t\"0.2.0\",\"name\":\"app\"}"
+        );
+    }
+}
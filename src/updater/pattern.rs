@@ -0,0 +1,165 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Context as _;
+use regex::Regex;
+
+use super::VersionUpdater;
+
+/// Replaces the `version` group of the first match of a regex in a file.
+pub struct Pattern {
+    name: String,
+    path: PathBuf,
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn new(path: PathBuf, pattern: &str) -> anyhow::Result<Self> {
+        let regex = Regex::new(pattern)?;
+
+        if !regex.capture_names().any(|name| name == Some("version")) {
+            return Err(anyhow::anyhow!(
+                "The pattern for {} has no `version` group: {}",
+                path.display(),
+                pattern
+            ));
+        }
+
+        Ok(Self {
+            name: "regex".to_string(),
+            path,
+            regex,
+        })
+    }
+
+    /// The top-level `version:` of a Helm `Chart.yaml`, leaving `appVersion` alone.
+    pub fn helm_chart(path: PathBuf) -> Self {
+        Self {
+            name: "Chart.yaml".to_string(),
+            path,
+            regex: Regex::new(r#"(?m)^version:[ \t]*["']?(?P<version>[^"'\s#]+)"#).unwrap(),
+        }
+    }
+
+    /// The first `version = "..."` in a Nix file, such as a derivation in `flake.nix`.
+    pub fn nix(path: PathBuf) -> Self {
+        Self {
+            name: "nix".to_string(),
+            path,
+            regex: Regex::new(r#"\bversion\s*=\s*"(?P<version>[^"]*)""#).unwrap(),
+        }
+    }
+}
+
+impl VersionUpdater for Pattern {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(vec![self.path.clone()])
+    }
+
+    fn set_version(&self, version: &semver::Version) -> anyhow::Result<Vec<PathBuf>> {
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+
+        let Some(current) = self
+            .regex
+            .captures(&content)
+            .and_then(|captures| captures.name("version"))
+        else {
+            return Err(anyhow::anyhow!(
+                "No version matching {} found in {}",
+                self.regex.as_str(),
+                self.path.display()
+            ));
+        };
+
+        if current.as_str() == version.to_string() {
+            return Ok(vec![]);
+        }
+
+        let updated = format!(
+            "{}{}{}",
+            &content[..current.start()],
+            version,
+            &content[current.end()..]
+        );
+
+        fs::write(&self.path, updated)?;
+
+        Ok(vec![self.path.clone()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_version(updater: fn(PathBuf) -> Pattern, content: &str, version: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "committer-pattern-{}-{}",
+            std::process::id(),
+            content.len()
+        ));
+        fs::write(&path, content).unwrap();
+
+        updater(path.clone())
+            .set_version(&semver::Version::parse(version).unwrap())
+            .unwrap();
+
+        let updated = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        updated
+    }
+
+    #[test]
+    fn updates_a_helm_chart() {
+        let content =
+            "apiVersion: v2\nname: app\nversion: \"0.1.0\" # the chart\nappVersion: 0.1.0\n";
+
+        assert_eq!(
+            set_version(Pattern::helm_chart, content, "0.2.0"),
+            "apiVersion: v2\nname: app\nversion: \"0.2.0\" # the chart\nappVersion: 0.1.0\n"
+        );
+    }
+
+    #[test]
+    fn skips_nested_helm_versions() {
+        let content =
+            "apiVersion: v2\ndependencies:\n  - name: db\n    version: 1.0.0\nversion: 0.1.0\n";
+
+        assert_eq!(
+            set_version(Pattern::helm_chart, content, "0.2.0"),
+            content.replace("version: 0.1.0", "version: 0.2.0")
+        );
+    }
+
+    #[test]
+    fn updates_a_nix_derivation() {
+        let content = "{\n  packages.default = pkgs.rustPlatform.buildRustPackage {\n    pname = \"app\";\n    version = \"0.1.0\";\n  };\n}\n";
+
+        assert_eq!(
+            set_version(Pattern::nix, content, "0.2.0"),
+            content.replace("\"0.1.0\"", "\"0.2.0\"")
+        );
+    }
+
+    #[test]
+    fn requires_a_version_group() {
+        let error = Pattern::new(PathBuf::from("app.txt"), r"v(\d+)")
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("no `version` group"));
+    }
+
+    #[test]
+    fn names_a_missing_file() {
+        let error = Pattern::nix(PathBuf::from("missing/flake.nix"))
+            .set_version(&semver::Version::new(1, 0, 0))
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "Failed to read missing/flake.nix");
+    }
+}
@@ -0,0 +1,71 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Context as _;
+
+use super::VersionUpdater;
+
+/// Replaces the whole content of a file like `VERSION` with the version.
+pub struct Plain {
+    pub path: PathBuf,
+}
+
+impl VersionUpdater for Plain {
+    fn name(&self) -> String {
+        "plain".to_string()
+    }
+
+    fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(vec![self.path.clone()])
+    }
+
+    fn set_version(&self, version: &semver::Version) -> anyhow::Result<Vec<PathBuf>> {
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+
+        if content.trim() == version.to_string() {
+            return Ok(vec![]);
+        }
+
+        fs::write(&self.path, format!("{}\n", version))?;
+
+        Ok(vec![self.path.clone()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_whole_file() {
+        let path = std::env::temp_dir().join(format!("committer-VERSION-{}", std::process::id()));
+        fs::write(&path, "0.1.0\n").unwrap();
+        let updater = Plain { path: path.clone() };
+
+        assert_eq!(
+            updater.set_version(&semver::Version::new(0, 2, 0)).unwrap(),
+            std::slice::from_ref(&path)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "0.2.0\n");
+        // Already at the version
+        assert!(updater
+            .set_version(&semver::Version::new(0, 2, 0))
+            .unwrap()
+            .is_empty());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn names_a_missing_file() {
+        let updater = Plain {
+            path: PathBuf::from("missing/VERSION"),
+        };
+
+        let error = updater
+            .set_version(&semver::Version::new(1, 0, 0))
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "Failed to read missing/VERSION");
+    }
+}
@@ -0,0 +1,126 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Context as _;
+use toml_edit::DocumentMut;
+
+use super::VersionUpdater;
+
+/// Updates `[project].version` and `[tool.poetry].version` in a `pyproject.toml`.
+pub struct Pyproject {
+    pub path: PathBuf,
+}
+
+impl VersionUpdater for Pyproject {
+    fn name(&self) -> String {
+        "pyproject.toml".to_string()
+    }
+
+    fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(vec![self.path.clone()])
+    }
+
+    fn set_version(&self, version: &semver::Version) -> anyhow::Result<Vec<PathBuf>> {
+        let mut document = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?
+            .parse::<DocumentMut>()?;
+
+        let mut found = false;
+        let mut changed = false;
+
+        // `Item::get_mut` would insert the keys that are missing, so look them up on the tables
+        if let Some(item) = document
+            .get_mut("project")
+            .and_then(|project| project.as_table_like_mut())
+            .and_then(|project| project.get_mut("version"))
+        {
+            found = true;
+            changed |= super::cargo::set_string(item, &version.to_string());
+        }
+
+        if let Some(item) = document
+            .get_mut("tool")
+            .and_then(|tool| tool.as_table_like_mut())
+            .and_then(|tool| tool.get_mut("poetry"))
+            .and_then(|poetry| poetry.as_table_like_mut())
+            .and_then(|poetry| poetry.get_mut("version"))
+        {
+            found = true;
+            changed |= super::cargo::set_string(item, &version.to_string());
+        }
+
+        if !found {
+            return Err(anyhow::anyhow!(
+                "{} has no [project] or [tool.poetry] version",
+                self.path.display()
+            ));
+        }
+
+        if !changed {
+            return Ok(vec![]);
+        }
+
+        fs::write(&self.path, document.to_string())?;
+
+        Ok(vec![self.path.clone()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_version(content: &str, version: &str) -> anyhow::Result<String> {
+        let path = std::env::temp_dir().join(format!(
+            "committer-pyproject-{}-{}.toml",
+            std::process::id(),
+            content.len()
+        ));
+        fs::write(&path, content).unwrap();
+
+        let updater = Pyproject { path: path.clone() };
+        let result = updater.set_version(&semver::Version::parse(version).unwrap());
+
+        let updated = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        result.map(|_| updated)
+    }
+
+    #[test]
+    fn updates_the_project_table() {
+        let content = "[project]\nname = \"app\"\nversion = \"0.1.0\" # bumped on release\n";
+
+        assert_eq!(
+            set_version(content, "0.2.0").unwrap(),
+            "[project]\nname = \"app\"\nversion = \"0.2.0\" # bumped on release\n"
+        );
+    }
+
+    #[test]
+    fn updates_the_poetry_table() {
+        let content = "[tool.poetry]\nname = \"app\"\nversion = \"0.1.0\"\n";
+
+        assert_eq!(
+            set_version(content, "0.2.0").unwrap(),
+            "[tool.poetry]\nname = \"app\"\nversion = \"0.2.0\"\n"
+        );
+    }
+
+    #[test]
+    fn skips_nested_versions() {
+        let content = "[project]\nname = \"app\"\nversion = \"0.1.0\"\n\n[tool.other]\nversion = \"0.1.0\"\n\n[tool.poetry.dependencies]\nversion = \"0.1.0\"\n";
+
+        assert_eq!(
+            set_version(content, "0.2.0").unwrap(),
+            content.replacen("0.1.0", "0.2.0", 1)
+        );
+    }
+
+    #[test]
+    fn requires_a_version() {
+        let error = set_version("[tool.other]\nversion = \"0.1.0\"\n", "0.2.0").unwrap_err();
+
+        assert!(error
+            .to_string()
+            .ends_with("has no [project] or [tool.poetry] version"));
+    }
+}